@@ -0,0 +1,19 @@
+use std::{env::args, error::Error, str::FromStr};
+
+use advent_utils::read_file;
+
+/// Input file, given as the first command line argument, `input.txt` by default.
+///
+/// The same file is used by `Solver::solve_env_config`, so extra modes of a binary
+/// should read it from here rather than from some other place
+pub fn input_path() -> String {
+    args().nth(1).unwrap_or_else(|| "input.txt".to_owned())
+}
+
+/// Read and parse the input file
+pub fn read_input<T>() -> Result<T, Box<dyn Error>>
+where
+    T: FromStr<Err = Box<dyn Error>>,
+{
+    read_file(input_path())?.parse()
+}
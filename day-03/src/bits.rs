@@ -0,0 +1,105 @@
+use std::str::FromStr;
+
+use displaydoc::Display;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Fixed-length bit vector, packed into 64-bit words.
+///
+/// Bit with index 0 is the most significant one, so bits are stored
+/// in the same order as they are written in the report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+/// Failed to parse binary report
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ParseBitsError {
+    /// Empty report
+    Empty,
+    /// Invalid character {found:?} at position {position}
+    InvalidCharacter { position: usize, found: char },
+}
+
+impl FromStr for Bits {
+    type Err = ParseBitsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(ParseBitsError::Empty);
+        }
+
+        let mut bits = Self::zeroed(s.chars().count());
+
+        for (position, c) in s.chars().enumerate() {
+            match c {
+                '0' => {}
+                '1' => bits.set(position),
+                found => return Err(ParseBitsError::InvalidCharacter { position, found }),
+            }
+        }
+
+        Ok(bits)
+    }
+}
+
+impl Bits {
+    pub fn zeroed(len: usize) -> Self {
+        Self {
+            words: vec![0; len.div_ceil(WORD_BITS)],
+            len,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> bool {
+        debug_assert!(index < self.len);
+
+        self.words[index / WORD_BITS] & (1 << (index % WORD_BITS)) != 0
+    }
+
+    pub fn set(&mut self, index: usize) {
+        debug_assert!(index < self.len);
+
+        self.words[index / WORD_BITS] |= 1 << (index % WORD_BITS);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn to_biguint(&self) -> BigUint {
+        let mut digits = vec![0u32; self.len.div_ceil(32)];
+
+        for index in (0..self.len).filter(|&index| self.get(index)) {
+            let power = self.len - index - 1;
+
+            digits[power / 32] |= 1 << (power % 32);
+        }
+
+        BigUint::new(digits)
+    }
+
+    /// Hex representation, zero-padded to the width of the vector
+    pub fn to_hex(&self) -> String {
+        format!(
+            "{:0>width$}",
+            self.to_biguint().to_str_radix(16),
+            width = self.len.div_ceil(4)
+        )
+    }
+}
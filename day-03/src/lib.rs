@@ -2,9 +2,20 @@ use std::{error::Error, str::FromStr};
 
 use advent_utils::{parse_raw_data, Part, Solver};
 
+pub use bits::{Bits, ParseBitsError};
+pub use rating::{Rating, RatingError, RatingKind, TieBreaking};
+
+use rating::ReportTrie;
+
+mod bits;
+mod rating;
+
 #[derive(Debug)]
 pub struct Solution {
-    reports: Vec<String>,
+    reports: Vec<Bits>,
+    /// for each bit position – set of reports, which have this bit set
+    columns: Vec<Bits>,
+    trie: ReportTrie,
     width: usize,
 }
 
@@ -12,11 +23,71 @@ impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(input_data: &str) -> Result<Self, Self::Err> {
-        let reports: Vec<String> = parse_raw_data(input_data)?;
+        let reports: Vec<Bits> = parse_raw_data(input_data)?;
+        let width = reports.first().ok_or("no reports found")?.len();
+
+        let mut columns = vec![Bits::zeroed(reports.len()); width];
+        let mut trie = ReportTrie::new(width);
+
+        for (i, report) in reports.iter().enumerate() {
+            if report.len() != width {
+                return Err(format!(
+                    "report #{} has width {}, expected {}",
+                    i,
+                    report.len(),
+                    width
+                )
+                .into());
+            }
+
+            for (position, column) in columns.iter_mut().enumerate() {
+                if report.get(position) {
+                    column.set(i);
+                }
+            }
+
+            trie.insert(i, report);
+        }
 
         Ok(Self {
-            width: reports[0].len(),
             reports,
+            columns,
+            trie,
+            width,
+        })
+    }
+}
+
+impl Solution {
+    /// Gamma and epsilon rates, built from the most and the least common bits
+    pub fn gamma_epsilon_rates(&self) -> (Bits, Bits) {
+        let mut gamma = Bits::zeroed(self.width);
+        let mut epsilon = Bits::zeroed(self.width);
+
+        for (position, column) in self.columns.iter().enumerate() {
+            let n_ones = column.count_ones();
+            let n_zeroes = self.reports.len() - n_ones;
+
+            if n_zeroes > n_ones {
+                epsilon.set(position);
+            } else {
+                gamma.set(position);
+            }
+        }
+
+        (gamma, epsilon)
+    }
+
+    pub fn rating(
+        &self,
+        kind: RatingKind,
+        tie_breaking: TieBreaking,
+    ) -> Result<Rating, RatingError> {
+        let index = self.trie.search(kind, tie_breaking)?;
+
+        Ok(Rating {
+            index,
+            value: self.reports[index].to_biguint(),
         })
     }
 }
@@ -25,110 +96,25 @@ impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
         match part {
             Part::One => {
-                let (zeroes, ones) = {
-                    let mut zeroes = vec![0; self.width];
-                    let mut ones = vec![0; self.width];
-
-                    for report in &self.reports {
-                        for (i, b) in report.bytes().enumerate() {
-                            match b {
-                                b'0' => zeroes[i] += 1,
-                                b'1' => ones[i] += 1,
-                                _ => unreachable!(),
-                            }
-                        }
-                    }
-
-                    (zeroes, ones)
-                };
+                let (gamma, epsilon_rate) = self.gamma_epsilon_rates();
 
-                let iter = zeroes
-                    .into_iter()
-                    .rev()
-                    .zip(ones.into_iter().rev())
-                    .enumerate();
-                let mut gamma = 0;
-                let mut epsilon_rate = 0;
-
-                for (i, (n_zeroes, n_ones)) in iter {
-                    if n_zeroes > n_ones {
-                        epsilon_rate |= 1 << i;
-                    } else {
-                        gamma |= 1 << i;
-                    }
-                }
-
-                format!("power consumption is {}", gamma * epsilon_rate)
+                format!(
+                    "power consumption is {}",
+                    gamma.to_biguint() * epsilon_rate.to_biguint()
+                )
             }
             Part::Two => {
-                let mut o2 = Vec::with_capacity(self.width);
-                let mut co2 = Vec::with_capacity(self.width);
-
-                let mut o2_reports = self
-                    .reports
-                    .iter()
-                    .map(|s| s.as_bytes())
-                    .collect::<Vec<_>>();
-                let mut co2_reports = self
-                    .reports
-                    .iter()
-                    .map(|s| s.as_bytes())
-                    .collect::<Vec<_>>();
-
-                for i in 0..self.width {
-                    if o2_reports.len() > 1 {
-                        let (n_zeroes, n_ones) = o2_reports.iter().fold(
-                            (0, 0),
-                            |(zeroes, ones), &report| match report[i] {
-                                b'0' => (zeroes + 1, ones),
-                                b'1' => (zeroes, ones + 1),
-                                _ => unreachable!(),
-                            },
-                        );
-
-                        if n_zeroes > n_ones {
-                            o2.push(b'0');
-                        } else {
-                            o2.push(b'1');
-                        }
-
-                        o2_reports.retain(|&s| s.starts_with(&o2));
-                    }
-
-                    if co2_reports.len() > 1 {
-                        let (n_zeroes, n_ones) =
-                            co2_reports
-                                .iter()
-                                .fold((0, 0), |(zeroes, ones), &report| match report[i] {
-                                    b'0' => (zeroes + 1, ones),
-                                    b'1' => (zeroes, ones + 1),
-                                    _ => unreachable!(),
-                                });
-
-                        if n_zeroes > n_ones {
-                            co2.push(b'1');
-                        } else {
-                            co2.push(b'0');
-                        }
-
-                        co2_reports.retain(|&s| s.starts_with(&co2));
-                    }
-                }
-
-                let o2 = match o2_reports[..] {
-                    [report] => usize::from_str_radix(std::str::from_utf8(report).unwrap(), 2),
-                    _ => return "not found suitable O2 report".to_owned(),
+                let o2 = match self.rating(RatingKind::O2Generator, TieBreaking::PreferOne) {
+                    Ok(o2) => o2,
+                    Err(e) => return e.to_string(),
                 };
 
-                let co2 = match co2_reports[..] {
-                    [report] => usize::from_str_radix(std::str::from_utf8(report).unwrap(), 2),
-                    _ => return "not found suitable O2 report".to_owned(),
+                let co2 = match self.rating(RatingKind::Co2Scrubber, TieBreaking::PreferZero) {
+                    Ok(co2) => co2,
+                    Err(e) => return e.to_string(),
                 };
 
-                match (o2, co2) {
-                    (Ok(o2), Ok(co2)) => format!("life support rating is {}", o2 * co2),
-                    _ => "failed to parse ratings".to_owned(),
-                }
+                format!("life support rating is {}", o2.value * co2.value)
             }
         }
     }
@@ -137,3 +123,111 @@ impl Solver for Solution {
         3
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::*;
+
+    #[test]
+    fn test_short_example() {
+        let solution: Solution = include_str!("../short.txt")
+            .parse()
+            .expect("failed to parse input");
+
+        let (gamma, epsilon_rate) = solution.gamma_epsilon_rates();
+        assert_eq!(gamma.to_biguint(), BigUint::from(22u32));
+        assert_eq!(epsilon_rate.to_biguint(), BigUint::from(9u32));
+
+        assert_eq!(
+            solution.rating(RatingKind::O2Generator, TieBreaking::PreferOne),
+            Ok(Rating {
+                index: 3,
+                value: BigUint::from(23u32)
+            })
+        );
+        assert_eq!(
+            solution.rating(RatingKind::Co2Scrubber, TieBreaking::PreferZero),
+            Ok(Rating {
+                index: 11,
+                value: BigUint::from(10u32)
+            })
+        );
+    }
+
+    #[test]
+    fn test_wide_reports() {
+        let ones = "1".repeat(100);
+        let almost_ones = format!("{}0", "1".repeat(99));
+        let zeroes = "0".repeat(100);
+        let input = format!("{}\n{}\n{}\n", ones, almost_ones, zeroes);
+
+        let solution: Solution = input.parse().expect("failed to parse input");
+        let (gamma, epsilon_rate) = solution.gamma_epsilon_rates();
+
+        assert_eq!(gamma.to_hex(), format!("{}e", "f".repeat(24)));
+        assert_eq!(epsilon_rate.to_hex(), format!("{}1", "0".repeat(24)));
+        assert_eq!(
+            solution
+                .rating(RatingKind::O2Generator, TieBreaking::PreferOne)
+                .map(|rating| rating.value),
+            Ok((BigUint::from(1u32) << 100) - 1u32)
+        );
+        assert_eq!(
+            solution
+                .rating(RatingKind::Co2Scrubber, TieBreaking::PreferZero)
+                .map(|rating| rating.index),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_tie_breaking() {
+        let solution: Solution = "010\n100\n".parse().expect("failed to parse input");
+
+        assert_eq!(
+            solution
+                .rating(RatingKind::O2Generator, TieBreaking::PreferOne)
+                .map(|rating| rating.index),
+            Ok(1)
+        );
+        assert_eq!(
+            solution
+                .rating(RatingKind::O2Generator, TieBreaking::PreferZero)
+                .map(|rating| rating.index),
+            Ok(0)
+        );
+        assert_eq!(
+            solution.rating(RatingKind::Co2Scrubber, TieBreaking::Error),
+            Err(RatingError::Tie {
+                kind: RatingKind::Co2Scrubber,
+                position: 0
+            })
+        );
+
+        let duplicates: Solution = "010\n010\n".parse().expect("failed to parse input");
+        assert_eq!(
+            duplicates.rating(RatingKind::O2Generator, TieBreaking::PreferOne),
+            Err(RatingError::Duplicates(RatingKind::O2Generator))
+        );
+        assert_eq!(
+            duplicates
+                .rating(RatingKind::Co2Scrubber, TieBreaking::PreferZero)
+                .map_err(|e| e.to_string()),
+            Err("not found suitable CO2 report".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_invalid_character() {
+        assert_eq!(
+            "0120".parse::<Bits>(),
+            Err(ParseBitsError::InvalidCharacter {
+                position: 2,
+                found: '2'
+            })
+        );
+        assert!("00100\n0x110\n".parse::<Solution>().is_err());
+    }
+}
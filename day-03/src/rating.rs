@@ -0,0 +1,141 @@
+use std::cmp::Ordering;
+
+use displaydoc::Display;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::bits::Bits;
+
+const ROOT: usize = 0;
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
+pub enum RatingKind {
+    /// O2
+    O2Generator,
+    /// CO2
+    Co2Scrubber,
+}
+
+/// Which bit to keep, when zeroes and ones are equally common
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreaking {
+    PreferOne,
+    PreferZero,
+    Error,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rating {
+    /// index of the selected report
+    pub index: usize,
+    pub value: BigUint,
+}
+
+/// Failed to find rating
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum RatingError {
+    /// not found suitable {0} report
+    NotFound(RatingKind),
+    /// bits at position {position} are equally common while searching for {kind} report
+    Tie { kind: RatingKind, position: usize },
+    /// several identical {0} reports are left
+    Duplicates(RatingKind),
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: [Option<usize>; 2],
+    /// number of reports in this subtree
+    count: usize,
+    /// index of the last report inserted in this subtree
+    report: usize,
+}
+
+/// Binary trie over reports, each node keeps number of reports with its prefix
+#[derive(Debug)]
+pub struct ReportTrie {
+    nodes: Vec<Node>,
+    width: usize,
+}
+
+impl ReportTrie {
+    pub fn new(width: usize) -> Self {
+        Self {
+            nodes: vec![Node::default()],
+            width,
+        }
+    }
+
+    pub fn insert(&mut self, index: usize, report: &Bits) {
+        debug_assert_eq!(report.len(), self.width);
+
+        let mut node = ROOT;
+
+        for position in 0..=self.width {
+            self.nodes[node].count += 1;
+            self.nodes[node].report = index;
+
+            if position == self.width {
+                break;
+            }
+
+            let bit = report.get(position) as usize;
+
+            node = match self.nodes[node].children[bit] {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children[bit] = Some(child);
+
+                    child
+                }
+            };
+        }
+    }
+
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |node| self.nodes[node].count)
+    }
+
+    /// Walk down the trie, following the most (for O2) or the least (for CO2) common bit,
+    /// until only one report is left. Returns index of that report.
+    pub fn search(
+        &self,
+        kind: RatingKind,
+        tie_breaking: TieBreaking,
+    ) -> Result<usize, RatingError> {
+        let mut node = &self.nodes[ROOT];
+
+        for position in 0..self.width {
+            if node.count <= 1 {
+                break;
+            }
+
+            let n_zeroes = self.count(node.children[0]);
+            let n_ones = self.count(node.children[1]);
+
+            let bit = match (n_ones.cmp(&n_zeroes), kind) {
+                (Ordering::Equal, _) => match tie_breaking {
+                    TieBreaking::PreferOne => 1,
+                    TieBreaking::PreferZero => 0,
+                    TieBreaking::Error => return Err(RatingError::Tie { kind, position }),
+                },
+                (Ordering::Greater, RatingKind::O2Generator)
+                | (Ordering::Less, RatingKind::Co2Scrubber) => 1,
+                _ => 0,
+            };
+
+            node = match node.children[bit] {
+                Some(child) => &self.nodes[child],
+                None => return Err(RatingError::NotFound(kind)),
+            };
+        }
+
+        match node.count {
+            0 => Err(RatingError::NotFound(kind)),
+            1 => Ok(node.report),
+            _ => Err(RatingError::Duplicates(kind)),
+        }
+    }
+}
@@ -1,61 +1,112 @@
-use std::{num::ParseIntError, str::FromStr};
+use std::{fmt, num::ParseIntError, str::FromStr};
 
-const SIDE: usize = 5;
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::pattern::WinPatterns;
 
 #[derive(Debug, Clone)]
 pub struct Board {
-    numbers: [u32; SIDE * SIDE],
-    marked: [bool; SIDE * SIDE],
-    row_marks: [usize; SIDE],
-    col_marks: [usize; SIDE],
-    won: bool,
+    side: usize,
+    numbers: Vec<u32>,
+    marked: Vec<bool>,
+    /// cells of the line, which completed the board
+    winning_line: Option<Vec<usize>>,
+}
+
+/// Failed to parse bingo board
+#[derive(Debug, Display, Error)]
+pub enum ParseBoardError {
+    /// Empty board
+    Empty,
+    /// Board is not square: row {row} has {len} numbers, expected {side}
+    NotSquare { row: usize, len: usize, side: usize },
+    /// Failed to parse number: {0}
+    ParseIntError(#[from] ParseIntError),
 }
 
 impl FromStr for Board {
-    type Err = ParseIntError;
+    type Err = ParseBoardError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let numbers: Vec<u32> = s
-            .split_ascii_whitespace()
-            .map(str::parse)
-            .collect::<Result<_, _>>()?;
+        let rows = s
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split_ascii_whitespace()
+                    .map(str::parse)
+                    .collect::<Result<Vec<u32>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let side = rows.len();
+        if side == 0 {
+            return Err(ParseBoardError::Empty);
+        }
+
+        if let Some((row, numbers)) = rows
+            .iter()
+            .enumerate()
+            .find(|(_, numbers)| numbers.len() != side)
+        {
+            return Err(ParseBoardError::NotSquare {
+                row,
+                len: numbers.len(),
+                side,
+            });
+        }
 
         Ok(Self {
-            won: false,
-            numbers: numbers.try_into().expect("bingo board of invalid size"),
-            marked: [false; SIDE * SIDE],
-            row_marks: [0; SIDE],
-            col_marks: [0; SIDE],
+            side,
+            numbers: rows.into_iter().flatten().collect(),
+            marked: vec![false; side * side],
+            winning_line: None,
         })
     }
 }
 
 impl Board {
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
     /// process called number, returning true, if this was a winning move for this board
-    pub fn process_call(&mut self, called_number: u32) -> bool {
-        if self.won {
+    pub fn process_call(&mut self, called_number: u32, patterns: &WinPatterns) -> bool {
+        debug_assert_eq!(self.side, patterns.side());
+
+        match self.numbers.iter().position(|&n| n == called_number) {
+            Some(cell) => self.mark(cell, patterns),
+            None => false,
+        }
+    }
+
+    /// mark cell, returning true, if this was a winning move for this board
+    pub fn mark(&mut self, cell: usize, patterns: &WinPatterns) -> bool {
+        if self.won() {
             return false;
         }
 
-        for (i, n) in self.numbers.iter().enumerate() {
-            if *n == called_number {
-                let row = i / SIDE;
-                let col = i % SIDE;
+        self.marked[cell] = true;
+        self.winning_line = patterns
+            .lines_through(cell)
+            .iter()
+            .map(|&line| patterns.line(line))
+            .find(|line| line.iter().all(|&cell| self.marked[cell]))
+            .map(<[usize]>::to_vec);
 
-                self.marked[i] = true;
-                self.col_marks[col] += 1;
-                self.row_marks[row] += 1;
+        self.won()
+    }
 
-                if self.col_marks[col] == SIDE || self.row_marks[row] == SIDE {
-                    self.won = true;
-                    return true;
-                }
+    pub fn won(&self) -> bool {
+        self.winning_line.is_some()
+    }
 
-                break;
-            }
-        }
+    pub fn winning_line(&self) -> Option<&[usize]> {
+        self.winning_line.as_deref()
+    }
 
-        false
+    pub fn numbers(&self) -> &[u32] {
+        &self.numbers
     }
 
     pub fn unmarked_sum(&self) -> u32 {
@@ -66,3 +117,38 @@ impl Board {
             .sum()
     }
 }
+
+/// Renders unmarked numbers as ` n `, marked as `(n)` and winning line as `[n]`
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self
+            .numbers
+            .iter()
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let winning_line = self.winning_line().unwrap_or_default();
+
+        for (row, numbers) in self.numbers.chunks(self.side).enumerate() {
+            for (col, n) in numbers.iter().enumerate() {
+                let cell = row * self.side + col;
+                let (open, close) = if winning_line.contains(&cell) {
+                    ('[', ']')
+                } else if self.marked[cell] {
+                    ('(', ')')
+                } else {
+                    (' ', ' ')
+                };
+
+                if col > 0 {
+                    write!(f, " ")?;
+                }
+                write!(f, "{}{:>width$}{}", open, n, close, width = width)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
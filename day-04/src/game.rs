@@ -0,0 +1,121 @@
+use rustc_hash::FxHashMap;
+
+use crate::{board::Board, pattern::WinPatterns};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    Call { turn: usize, number: u32 },
+    Marked { board: usize, cell: usize },
+    Won { board: usize, score: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finish {
+    pub board: usize,
+    /// index of the winning call
+    pub turn: usize,
+    pub called: u32,
+    pub score: u32,
+}
+
+/// Full record of a played bingo game
+#[derive(Debug)]
+pub struct Game {
+    events: Vec<Event>,
+    ranking: Vec<Finish>,
+    unfinished: Vec<usize>,
+}
+
+impl Game {
+    /// Play all calls on all boards
+    ///
+    /// Numbers are indexed to their `(board, cell)` positions once,
+    /// so each call touches only the boards, which actually contain it.
+    pub fn simulate(calls: &[u32], boards: &[Board], patterns: &WinPatterns) -> Self {
+        let mut positions: FxHashMap<u32, Vec<(usize, usize)>> = FxHashMap::default();
+
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (cell, &number) in board.numbers().iter().enumerate() {
+                let entries = positions.entry(number).or_default();
+
+                // only first occurrence of a number on each board is marked
+                if entries.last().map(|&(last, _)| last) != Some(board_idx) {
+                    entries.push((board_idx, cell));
+                }
+            }
+        }
+
+        let mut boards = boards.to_vec();
+        let mut events = Vec::with_capacity(calls.len());
+        let mut ranking = Vec::new();
+
+        for (turn, &number) in calls.iter().enumerate() {
+            events.push(Event::Call { turn, number });
+
+            if ranking.len() == boards.len() {
+                continue;
+            }
+
+            for &(board_idx, cell) in positions.get(&number).into_iter().flatten() {
+                let board = &mut boards[board_idx];
+                if board.won() {
+                    continue;
+                }
+
+                events.push(Event::Marked {
+                    board: board_idx,
+                    cell,
+                });
+
+                if board.mark(cell, patterns) {
+                    let score = number * board.unmarked_sum();
+
+                    events.push(Event::Won {
+                        board: board_idx,
+                        score,
+                    });
+                    ranking.push(Finish {
+                        board: board_idx,
+                        turn,
+                        called: number,
+                        score,
+                    });
+                }
+            }
+        }
+
+        let unfinished = boards
+            .iter()
+            .enumerate()
+            .filter_map(|(i, board)| (!board.won()).then_some(i))
+            .collect();
+
+        Self {
+            events,
+            ranking,
+            unfinished,
+        }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Boards in the order they have won
+    pub fn ranking(&self) -> &[Finish] {
+        &self.ranking
+    }
+
+    pub fn first_winner(&self) -> Option<&Finish> {
+        self.ranking.first()
+    }
+
+    pub fn last_winner(&self) -> Option<&Finish> {
+        self.ranking.last()
+    }
+
+    /// Boards, which haven't won after all calls
+    pub fn unfinished(&self) -> &[usize] {
+        &self.unfinished
+    }
+}
@@ -1,15 +1,23 @@
-use std::{error::Error, str::FromStr};
+use std::{cell::OnceCell, error::Error, str::FromStr};
 
 use advent_utils::{Part, Solver};
 
-use board::Board;
+pub use board::{Board, ParseBoardError};
+pub use game::{Event, Finish, Game};
+pub use pattern::{PatternError, WinPattern, WinPatterns};
+pub use replay::Replay;
 
 mod board;
+mod game;
+mod pattern;
+mod replay;
 
 #[derive(Debug)]
 pub struct Solution {
     calls: Vec<u32>,
     boards: Vec<Board>,
+    patterns: WinPatterns,
+    game: OnceCell<Game>,
 }
 
 impl FromStr for Solution {
@@ -20,54 +28,206 @@ impl FromStr for Solution {
 
         let calls = calls.split(',').map(str::parse).collect::<Result<_, _>>()?;
 
-        let boards = boards
+        let boards: Vec<Board> = boards
             .split("\n\n")
             .map(str::parse)
             .collect::<Result<_, _>>()?;
 
-        Ok(Self { calls, boards })
+        let side = boards.first().ok_or("no boards found")?.side();
+        if let Some((i, board)) = boards
+            .iter()
+            .enumerate()
+            .find(|(_, board)| board.side() != side)
+        {
+            return Err(
+                format!("board #{} has side {}, expected {}", i, board.side(), side).into(),
+            );
+        }
+
+        Ok(Self {
+            calls,
+            boards,
+            patterns: WinPatterns::rows_and_columns(side)?,
+            game: OnceCell::new(),
+        })
     }
 }
 
-impl Solver for Solution {
-    fn solve(&self, part: Part) -> String {
-        let mut boards = self.boards.clone();
-
-        match part {
-            Part::One => {
-                for called in &self.calls {
-                    for board in &mut boards {
-                        if board.process_call(*called) {
-                            return format!(
-                                "first winner score is {}",
-                                called * board.unmarked_sum()
-                            );
-                        }
-                    }
-                }
+impl Solution {
+    /// Replace default (rows and columns) win patterns
+    pub fn with_win_patterns(mut self, patterns: &[WinPattern]) -> Result<Self, PatternError> {
+        self.patterns = WinPatterns::new(self.patterns.side(), patterns)?;
+        self.game = OnceCell::new();
 
-                "no winning boards found :(".to_owned()
-            }
-            Part::Two => {
-                let mut scores = vec![];
-
-                for called in &self.calls {
-                    for board in boards.iter_mut() {
-                        if board.process_call(*called) {
-                            scores.push(called * board.unmarked_sum());
-                        }
-                    }
-                }
+        Ok(self)
+    }
+
+    /// Game played with all calls, simulated once on first access
+    pub fn game(&self) -> &Game {
+        self.game
+            .get_or_init(|| Game::simulate(&self.calls, &self.boards, &self.patterns))
+    }
 
-                match scores.last() {
-                    Some(score) => format!("last winner score is {}", score),
-                    None => "no winning boards found :(".to_owned(),
+    pub fn replay(&self) -> Replay<'_> {
+        Replay::new(&self.calls, &self.boards, &self.patterns)
+    }
+
+    /// Render state of all boards after each call or, if `after_call` is given,
+    /// only after the call with that index
+    pub fn render_replay(&self, after_call: Option<usize>) -> String {
+        let mut replay = self.replay();
+        let mut rendered = String::new();
+
+        while replay.step().is_some() {
+            match after_call {
+                Some(call) if call + 1 == replay.turn() => {
+                    rendered.push_str(&replay.to_string());
+                    break;
                 }
+                Some(_) => {}
+                None => rendered.push_str(&replay.to_string()),
             }
         }
+
+        rendered
+    }
+}
+
+impl Solver for Solution {
+    fn solve(&self, part: Part) -> String {
+        let (winner, description) = match part {
+            Part::One => (self.game().first_winner(), "first"),
+            Part::Two => (self.game().last_winner(), "last"),
+        };
+
+        match winner {
+            Some(finish) => format!("{} winner score is {}", description, finish.score),
+            None => "no winning boards found :(".to_owned(),
+        }
     }
 
     fn day_number() -> u32 {
         4
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_example() {
+        let solution: Solution = include_str!("../short.txt")
+            .parse()
+            .expect("failed to parse input");
+
+        assert_eq!(solution.solve(Part::One), "first winner score is 4512");
+        assert_eq!(solution.solve(Part::Two), "last winner score is 1924");
+    }
+
+    #[test]
+    fn test_game_ranking() {
+        let solution: Solution = include_str!("../short.txt")
+            .parse()
+            .expect("failed to parse input");
+        let game = solution.game();
+
+        assert_eq!(
+            game.ranking()
+                .iter()
+                .map(|finish| (finish.board, finish.called, finish.score))
+                .collect::<Vec<_>>(),
+            [(2, 24, 4512), (0, 16, 2192), (1, 13, 1924)]
+        );
+        assert!(game.unfinished().is_empty());
+
+        assert_eq!(
+            game.events()[..3],
+            [
+                Event::Call { turn: 0, number: 7 },
+                Event::Marked { board: 0, cell: 14 },
+                Event::Marked { board: 1, cell: 12 },
+            ]
+        );
+        assert_eq!(
+            game.events()
+                .iter()
+                .rfind(|event| matches!(event, Event::Won { .. })),
+            Some(&Event::Won {
+                board: 1,
+                score: 1924
+            })
+        );
+    }
+
+    #[test]
+    fn test_replay() {
+        let solution: Solution = "4,2,5\n\n1 2\n3 4\n\n5 6\n7 8".parse().unwrap();
+
+        assert_eq!(
+            solution.render_replay(Some(1)),
+            [
+                "call #1: 2",
+                "board #0 – won:",
+                " 1  [2]",
+                " 3  [4]",
+                "",
+                "board #1:",
+                " 5   6 ",
+                " 7   8 ",
+                "",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!(solution.render_replay(None).matches("call #").count(), 3);
+        assert_eq!(solution.render_replay(Some(3)), "");
+    }
+
+    #[test]
+    fn test_win_patterns() {
+        let input = "1,5,9,3,7\n\n1 2 3\n4 5 6\n7 8 9\n\n9 8 7\n6 5 4\n3 2 1";
+
+        let diagonals: Solution = input
+            .parse::<Solution>()
+            .unwrap()
+            .with_win_patterns(&[WinPattern::Diagonals])
+            .unwrap();
+        assert_eq!(diagonals.solve(Part::One), "first winner score is 270");
+
+        let corners: Solution = input
+            .parse::<Solution>()
+            .unwrap()
+            .with_win_patterns(&[WinPattern::FourCorners])
+            .unwrap();
+        assert_eq!(corners.solve(Part::One), "first winner score is 140");
+
+        assert_eq!(
+            input
+                .parse::<Solution>()
+                .unwrap()
+                .with_win_patterns(&[WinPattern::Mask(vec![(0, 3)])])
+                .unwrap_err(),
+            PatternError::OutOfBounds(0, 3)
+        );
+        assert_eq!(
+            WinPatterns::new(0, &[WinPattern::FourCorners]).unwrap_err(),
+            PatternError::EmptyBoard
+        );
+    }
+
+    #[test]
+    fn test_invalid_board_size() {
+        assert!("1,2\n\n1 2\n3 4\n\n1 2 3\n4 5 6\n7 8 9"
+            .parse::<Solution>()
+            .is_err());
+        assert!(matches!(
+            "1 2\n3".parse::<Board>(),
+            Err(ParseBoardError::NotSquare {
+                row: 1,
+                len: 1,
+                side: 2
+            })
+        ));
+    }
+}
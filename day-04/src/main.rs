@@ -1,9 +1,24 @@
-use std::error::Error;
+use std::{env::var, error::Error};
 
+use advent_input::read_input;
 use advent_utils::Solver;
 
 use day_04::Solution;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    Solution::solve_env_config()
+    // `REPLAY=all` prints state of all boards after each call,
+    // `REPLAY=<n>` – only after the call with index n
+    let Ok(replay) = var("REPLAY") else {
+        return Solution::solve_env_config();
+    };
+
+    let solution: Solution = read_input()?;
+    let after_call = match replay.as_str() {
+        "all" => None,
+        call => Some(call.parse()?),
+    };
+
+    print!("{}", solution.render_replay(after_call));
+
+    Ok(())
 }
@@ -0,0 +1,133 @@
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Set of cells, which should be marked for a board to win
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WinPattern {
+    /// any full row
+    Rows,
+    /// any full column
+    Columns,
+    /// any of two main diagonals
+    Diagonals,
+    FourCorners,
+    FullCard,
+    /// custom set of `(row, col)` cells
+    Mask(Vec<(usize, usize)>),
+}
+
+/// Invalid win pattern
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum PatternError {
+    /// No win patterns given
+    Empty,
+    /// Board has no cells
+    EmptyBoard,
+    /// Empty mask
+    EmptyMask,
+    /// Cell ({0}, {1}) is out of board bounds
+    OutOfBounds(usize, usize),
+}
+
+impl WinPattern {
+    fn lines(&self, side: usize) -> Result<Vec<Vec<usize>>, PatternError> {
+        let lines = match self {
+            Self::Rows => (0..side)
+                .map(|row| (0..side).map(|col| row * side + col).collect())
+                .collect(),
+            Self::Columns => (0..side)
+                .map(|col| (0..side).map(|row| row * side + col).collect())
+                .collect(),
+            Self::Diagonals => vec![
+                (0..side).map(|i| i * side + i).collect(),
+                (0..side).map(|i| i * side + (side - i - 1)).collect(),
+            ],
+            Self::FourCorners => {
+                let mut corners = vec![0, side - 1, (side - 1) * side, side * side - 1];
+                corners.sort_unstable();
+                corners.dedup();
+
+                vec![corners]
+            }
+            Self::FullCard => vec![(0..side * side).collect()],
+            Self::Mask(cells) => {
+                if cells.is_empty() {
+                    return Err(PatternError::EmptyMask);
+                }
+
+                let mut line = cells
+                    .iter()
+                    .map(|&(row, col)| {
+                        if row < side && col < side {
+                            Ok(row * side + col)
+                        } else {
+                            Err(PatternError::OutOfBounds(row, col))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                line.sort_unstable();
+                line.dedup();
+
+                vec![line]
+            }
+        };
+
+        Ok(lines)
+    }
+}
+
+/// Win patterns, expanded into lines of cells for boards of the given side
+#[derive(Debug, Clone)]
+pub struct WinPatterns {
+    side: usize,
+    lines: Vec<Vec<usize>>,
+    /// for each cell – indices of lines, containing it
+    cell_lines: Vec<Vec<usize>>,
+}
+
+impl WinPatterns {
+    pub fn new(side: usize, patterns: &[WinPattern]) -> Result<Self, PatternError> {
+        if patterns.is_empty() {
+            return Err(PatternError::Empty);
+        }
+        if side == 0 {
+            return Err(PatternError::EmptyBoard);
+        }
+
+        let mut lines = Vec::new();
+        for pattern in patterns {
+            lines.extend(pattern.lines(side)?);
+        }
+
+        let mut cell_lines = vec![vec![]; side * side];
+        for (i, line) in lines.iter().enumerate() {
+            for &cell in line {
+                cell_lines[cell].push(i);
+            }
+        }
+
+        Ok(Self {
+            side,
+            lines,
+            cell_lines,
+        })
+    }
+
+    /// Default AoC rules – any full row or column wins
+    pub fn rows_and_columns(side: usize) -> Result<Self, PatternError> {
+        Self::new(side, &[WinPattern::Rows, WinPattern::Columns])
+    }
+
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    pub fn line(&self, index: usize) -> &[usize] {
+        &self.lines[index]
+    }
+
+    /// Indices of lines, which contain given cell
+    pub fn lines_through(&self, cell: usize) -> &[usize] {
+        &self.cell_lines[cell]
+    }
+}
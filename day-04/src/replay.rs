@@ -0,0 +1,63 @@
+use std::fmt;
+
+use crate::{board::Board, pattern::WinPatterns};
+
+/// Step-by-step replay of a bingo game, keeping state of every board
+#[derive(Debug)]
+pub struct Replay<'a> {
+    calls: &'a [u32],
+    patterns: &'a WinPatterns,
+    boards: Vec<Board>,
+    turn: usize,
+}
+
+impl<'a> Replay<'a> {
+    pub fn new(calls: &'a [u32], boards: &[Board], patterns: &'a WinPatterns) -> Self {
+        Self {
+            calls,
+            patterns,
+            boards: boards.to_vec(),
+            turn: 0,
+        }
+    }
+
+    /// Process next call, returning called number or `None`, if all calls were made
+    pub fn step(&mut self) -> Option<u32> {
+        let called = *self.calls.get(self.turn)?;
+
+        for board in &mut self.boards {
+            board.process_call(called, self.patterns);
+        }
+        self.turn += 1;
+
+        Some(called)
+    }
+
+    /// Number of calls made so far
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+}
+
+/// Renders state of all boards after the last made call
+impl fmt::Display for Replay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.turn.checked_sub(1) {
+            Some(last) => writeln!(f, "call #{}: {}", last, self.calls[last])?,
+            None => writeln!(f, "before first call")?,
+        }
+
+        for (i, board) in self.boards.iter().enumerate() {
+            let status = if board.won() { " – won" } else { "" };
+
+            writeln!(f, "board #{}{}:", i, status)?;
+            writeln!(f, "{}", board)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use crate::{
+    line::{LineSegment, Orientation},
+    overlap::{build_runs, find_crossings, key, Crossing, Interval, Point, Run, Step},
+};
+
+/// Spatial index over vent lines
+///
+/// Collinear segments are merged into runs with constant coverage, so only
+/// crossings of runs with different directions are stored point by point.
+#[derive(Debug)]
+pub struct VentIndex {
+    /// intervals of all segments with their indices, sorted by line
+    segments: Vec<(Interval, usize)>,
+    steps: Vec<Step>,
+    runs: Vec<Run>,
+    /// sorted by point
+    crossings: Vec<Crossing>,
+    line_counts: BTreeMap<Orientation, usize>,
+}
+
+impl VentIndex {
+    pub fn new<'a, I>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = &'a LineSegment>,
+    {
+        let mut segments = lines
+            .into_iter()
+            .enumerate()
+            .map(|(i, line)| (Interval::new(line), i))
+            .collect::<Vec<_>>();
+        segments.sort_unstable();
+
+        let mut line_counts = BTreeMap::new();
+        for (interval, _) in &segments {
+            *line_counts
+                .entry(Orientation::of_step(interval.step))
+                .or_default() += 1;
+        }
+
+        let mut steps = segments
+            .iter()
+            .map(|(interval, _)| interval.step)
+            .collect::<Vec<_>>();
+        steps.dedup();
+
+        let runs = build_runs(segments.iter().map(|&(interval, _)| interval).collect());
+
+        let crossings = find_crossings(&runs);
+
+        Self {
+            segments,
+            steps,
+            runs,
+            crossings,
+            line_counts,
+        }
+    }
+
+    fn is_crossing(&self, point: Point) -> bool {
+        self.crossings
+            .binary_search_by_key(&point, |crossing| crossing.point)
+            .is_ok()
+    }
+
+    fn crossing_coverage(&self, crossing: &Crossing) -> u32 {
+        crossing.runs.iter().map(|&run| self.runs[run].count).sum()
+    }
+
+    /// Number of points with at least `min_overlap` lines, taking into account
+    /// only included runs and, optionally, only points within rectangle
+    fn count(
+        &self,
+        min_overlap: u32,
+        include: impl Fn(&Run) -> bool,
+        within: Option<(Point, Point)>,
+    ) -> usize {
+        let mut total = self
+            .runs
+            .iter()
+            .filter(|&run| run.count >= min_overlap && include(run))
+            .map(|run| match within {
+                Some((min, max)) => run.n_points_within(min, max),
+                None => run.n_points(),
+            })
+            .sum::<usize>();
+
+        let crossings = self.crossings.iter().filter(|crossing| {
+            within.is_none_or(|(min, max)| {
+                (min.0..=max.0).contains(&crossing.point.0)
+                    && (min.1..=max.1).contains(&crossing.point.1)
+            })
+        });
+
+        // coverage at crossing is the sum of coverages of all runs passing through it,
+        // while each of those runs has already counted it by its own coverage
+        for crossing in crossings {
+            let (coverage, already_counted) = crossing
+                .runs
+                .iter()
+                .map(|&run| &self.runs[run])
+                .filter(|&run| include(run))
+                .fold((0, 0), |(coverage, already_counted), run| {
+                    (
+                        coverage + run.count,
+                        already_counted + usize::from(run.count >= min_overlap),
+                    )
+                });
+
+            total -= already_counted;
+            total += usize::from(coverage > 0 && coverage >= min_overlap);
+        }
+
+        total
+    }
+
+    /// Number of points, covered by at least `min_overlap` lines
+    pub fn count_overlaps(&self, min_overlap: u32) -> usize {
+        self.count(min_overlap, |_| true, None)
+    }
+
+    /// Number of points, covered by at least `min_overlap` lines of given orientations
+    pub fn count_overlaps_among(&self, min_overlap: u32, orientations: &[Orientation]) -> usize {
+        self.count(
+            min_overlap,
+            |run| orientations.contains(&Orientation::of_step(run.step)),
+            None,
+        )
+    }
+
+    /// Number of points within rectangle `min..=max`, covered by at least `min_overlap` lines
+    pub fn count_overlaps_within(&self, min_overlap: u32, min: Point, max: Point) -> usize {
+        self.count(min_overlap, |_| true, Some((min, max)))
+    }
+
+    /// Indices of all segments, passing through the point
+    pub fn segments_through(&self, point: Point) -> Vec<usize> {
+        let mut found = Vec::new();
+
+        for &step in &self.steps {
+            let key = key(step, point);
+            let from = self
+                .segments
+                .partition_point(|(interval, _)| (interval.step, interval.key) < (step, key));
+
+            found.extend(
+                self.segments[from..]
+                    .iter()
+                    .take_while(|(interval, _)| interval.step == step && interval.key == key)
+                    .filter(|(interval, _)| interval.contains(point))
+                    .map(|&(_, i)| i),
+            );
+        }
+
+        found.sort_unstable();
+
+        found
+    }
+
+    /// Up to `n` points with the highest coverage, the hottest first
+    /// (points with the same coverage are ordered by coordinates)
+    pub fn hottest_cells(&self, n: usize) -> Vec<(Point, u32)> {
+        let sort_and_truncate = |cells: &mut Vec<(Point, u32)>| {
+            cells.sort_unstable_by_key(|&(point, coverage)| (Reverse(coverage), point));
+            cells.truncate(n);
+        };
+
+        let mut hottest = self
+            .crossings
+            .iter()
+            .map(|crossing| (crossing.point, self.crossing_coverage(crossing)))
+            .collect::<Vec<_>>();
+        sort_and_truncate(&mut hottest);
+
+        let mut runs = self.runs.iter().collect::<Vec<_>>();
+        runs.sort_unstable_by_key(|run| Reverse(run.count));
+
+        for run in runs {
+            // runs are sorted by coverage, so colder ones can't get into the result
+            if hottest.len() >= n && hottest.last().is_none_or(|&(_, c)| c > run.count) {
+                break;
+            }
+
+            hottest.extend(
+                run.points()
+                    .filter(|&point| !self.is_crossing(point))
+                    .take(n)
+                    .map(|point| (point, run.count)),
+            );
+            sort_and_truncate(&mut hottest);
+        }
+
+        hottest
+    }
+
+    /// Number of lines of given orientation
+    pub fn count_lines(&self, orientation: Orientation) -> usize {
+        self.line_counts.get(&orientation).copied().unwrap_or(0)
+    }
+}
@@ -1,63 +1,55 @@
 use std::{error::Error, str::FromStr};
 
 use advent_utils::{parse_raw_data, Part, Solver};
-use rustc_hash::FxHashMap;
 
-use line::LineSegment;
+pub use index::VentIndex;
+pub use line::{LineSegment, Orientation, ParseLineError};
+pub use overlap::count_overlaps;
 
+mod index;
 mod line;
+mod overlap;
 
 #[derive(Debug)]
 pub struct Solution {
     lines: Vec<LineSegment>,
+    index: VentIndex,
 }
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let lines = parse_raw_data(s)?;
+        let lines: Vec<LineSegment> = parse_raw_data(s)?;
+        let index = VentIndex::new(&lines);
 
-        Ok(Self { lines })
+        Ok(Self { lines, index })
+    }
+}
+
+impl Solution {
+    pub fn lines(&self) -> &[LineSegment] {
+        &self.lines
+    }
+
+    /// Index over parsed lines, segment indices in queries refer to `lines`
+    pub fn index(&self) -> &VentIndex {
+        &self.index
     }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
         match part {
-            Part::One => {
-                let lines = self
-                    .lines
-                    .iter()
-                    .filter(|&line| line.is_horizontal() || line.is_vertical());
-
-                let mut points: FxHashMap<_, u32> = FxHashMap::default();
-
-                for line in lines {
-                    for point in line.to_int_points() {
-                        *points.entry(point).or_default() += 1;
-                    }
-                }
-
-                format!(
-                    "there is {} points with overlap",
-                    points.values().filter(|&&v| v > 1).count()
-                )
-            }
-            Part::Two => {
-                let mut points: FxHashMap<_, u32> = FxHashMap::default();
-
-                for line in &self.lines {
-                    for point in line.to_int_points() {
-                        *points.entry(point).or_default() += 1;
-                    }
-                }
-
-                format!(
-                    "there is {} points with overlap",
-                    points.values().filter(|&&v| v > 1).count()
-                )
-            }
+            Part::One => format!(
+                "there is {} points with overlap",
+                self.index
+                    .count_overlaps_among(2, &[Orientation::Horizontal, Orientation::Vertical])
+            ),
+            Part::Two => format!(
+                "there is {} points with overlap",
+                self.index.count_overlaps(2)
+            ),
         }
     }
 
@@ -65,3 +57,191 @@ impl Solver for Solution {
         5
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashMap;
+
+    use super::*;
+
+    fn count_overlaps_by_points(lines: &[LineSegment], min_overlap: u32) -> usize {
+        let mut points: FxHashMap<_, u32> = FxHashMap::default();
+
+        for line in lines {
+            for point in line.to_int_points() {
+                *points.entry(point).or_default() += 1;
+            }
+        }
+
+        points.values().filter(|&&v| v >= min_overlap).count()
+    }
+
+    #[test]
+    fn test_short_example() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+
+        assert_eq!(solution.solve(Part::One), "there is 5 points with overlap");
+        assert_eq!(solution.solve(Part::Two), "there is 12 points with overlap");
+    }
+
+    #[test]
+    fn test_index_queries() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        let index = solution.index();
+
+        assert_eq!(index.segments_through((7, 4)), [2, 4]);
+        assert_eq!(index.segments_through((0, 9)), [0, 6]);
+        assert!(index.segments_through((1, 2)).is_empty());
+
+        assert_eq!(index.count_overlaps_within(2, (0, 0), (4, 4)), 3);
+        assert_eq!(index.count_overlaps_within(2, (0, 0), (9, 9)), 12);
+        assert_eq!(index.count_overlaps_within(2, (10, 10), (20, 20)), 0);
+        assert_eq!(
+            index.count_overlaps_within(2, (i64::MIN, i64::MIN), (i64::MAX, i64::MAX)),
+            12
+        );
+        assert_eq!(
+            index.count_overlaps_within(2, (i64::MIN, 0), (i64::MAX, 0)),
+            index.count_overlaps_within(2, (0, 0), (9, 0))
+        );
+
+        assert_eq!(
+            index.hottest_cells(3),
+            [((4, 4), 3), ((6, 4), 3), ((0, 9), 2)]
+        );
+
+        assert_eq!(index.count_lines(Orientation::Horizontal), 4);
+        assert_eq!(index.count_lines(Orientation::Vertical), 2);
+        assert_eq!(index.count_lines(Orientation::Diagonal), 4);
+        assert_eq!(index.count_lines(Orientation::Other), 0);
+    }
+
+    #[test]
+    fn test_arbitrary_slopes() {
+        let line: LineSegment = "1,1 -> 7,-8".parse().unwrap();
+        assert_eq!(line.step(), (2, -3));
+        assert_eq!(line.to_int_points(), [(1, 1), (3, -2), (5, -5), (7, -8)]);
+
+        let point: LineSegment = "3,3 -> 3,3".parse().unwrap();
+        assert!(point.is_point() && !point.is_diagonal());
+        assert_eq!(point.to_int_points(), [(3, 3)]);
+
+        let extreme: LineSegment = "-2147483648,-2147483648 -> 2147483647,2147483647"
+            .parse()
+            .unwrap();
+        assert!(extreme.is_diagonal());
+
+        let lines = [
+            "0,0 -> 6,3",
+            "0,3 -> 6,0",
+            "2,1 -> 4,2",
+            "3,0 -> 3,3",
+            "3,3 -> 3,3",
+        ]
+        .iter()
+        .map(|line| line.parse().unwrap())
+        .collect::<Vec<LineSegment>>();
+
+        // (2, 1), (4, 2) and (3, 3) are covered twice, (3, 1.5) is not an integer point
+        assert_eq!(count_overlaps(&lines, 2), 3);
+        assert_eq!(count_overlaps_by_points(&lines, 2), 3);
+    }
+
+    #[test]
+    fn test_matches_point_counting() {
+        // deterministic pseudo-random lines, dense enough to overlap a lot
+        let mut seed = 42u64;
+        let mut next = |modulo: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % modulo) as i32
+        };
+
+        let lines = (0..300)
+            .map(|_| {
+                let (x, y) = (next(60) - 10, next(60) - 10);
+                let len = next(25);
+                let (dx, dy) = if next(4) == 0 {
+                    // arbitrary slope, with several integer points on the way
+                    (next(7) - 3, next(7) - 3)
+                } else {
+                    [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 1), (0, -1)][next(6) as usize]
+                };
+
+                format!("{},{} -> {},{}", x, y, x + dx * len, y + dy * len)
+                    .parse()
+                    .unwrap()
+            })
+            .collect::<Vec<LineSegment>>();
+
+        for min_overlap in 1..=4 {
+            assert_eq!(
+                count_overlaps(&lines, min_overlap),
+                count_overlaps_by_points(&lines, min_overlap),
+                "min_overlap = {}",
+                min_overlap
+            );
+        }
+
+        let index = VentIndex::new(&lines);
+        let mut coverage: FxHashMap<_, u32> = FxHashMap::default();
+        for line in &lines {
+            for (x, y) in line.to_int_points() {
+                *coverage.entry((i64::from(x), i64::from(y))).or_default() += 1;
+            }
+        }
+
+        let max_coverage = coverage.values().copied().max().unwrap();
+        assert!(index
+            .hottest_cells(5)
+            .iter()
+            .all(|&(point, c)| c == coverage[&point] && c <= max_coverage));
+        assert_eq!(index.hottest_cells(1)[0].1, max_coverage);
+
+        for (&point, &c) in coverage.iter().step_by(17) {
+            assert_eq!(index.segments_through(point).len(), c as usize);
+        }
+
+        let (min, max) = ((5, -3), (30, 20));
+        let within = coverage
+            .iter()
+            .filter(|&(&(x, y), &c)| {
+                c >= 2 && (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y)
+            })
+            .count();
+        assert_eq!(index.count_overlaps_within(2, min, max), within);
+    }
+
+    #[test]
+    fn test_large_map() {
+        // long lines, so that most of them cross many others
+        let mut seed = 7u64;
+        let mut next = |modulo: u64| {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % modulo) as i32
+        };
+
+        let lines = (0..5_000)
+            .map(|_| {
+                let (x, y) = (next(1000), next(1000));
+                let len = next(200);
+                let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1), (2, 1), (1, -3)][next(6) as usize];
+                format!("{},{} -> {},{}", x, y, x + dx * len, y + dy * len)
+                    .parse()
+                    .unwrap()
+            })
+            .collect::<Vec<LineSegment>>();
+
+        for min_overlap in [2, 3] {
+            assert_eq!(
+                count_overlaps(&lines, min_overlap),
+                count_overlaps_by_points(&lines, min_overlap),
+                "min_overlap = {}",
+                min_overlap
+            );
+        }
+    }
+}
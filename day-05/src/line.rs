@@ -9,6 +9,34 @@ pub struct LineSegment {
     end: (i32, i32),
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Orientation {
+    Horizontal,
+    Vertical,
+    /// 45 degrees
+    Diagonal,
+    /// any other slope
+    Other,
+}
+
+impl Orientation {
+    pub const ALL: [Self; 4] = [
+        Self::Horizontal,
+        Self::Vertical,
+        Self::Diagonal,
+        Self::Other,
+    ];
+
+    pub(crate) fn of_step(step: (i64, i64)) -> Self {
+        match step {
+            (1, 0) => Self::Horizontal,
+            (0, 1) => Self::Vertical,
+            (1, 1) | (1, -1) => Self::Diagonal,
+            _ => Self::Other,
+        }
+    }
+}
+
 #[derive(Debug, Display, Error)]
 /// Failed to parse line
 pub enum ParseLineError {
@@ -44,49 +72,83 @@ impl LineSegment {
     }
 
     pub fn is_diagonal(&self) -> bool {
-        let a = self.end.0 - self.start.0;
-        let b = self.end.1 - self.start.1;
+        let (dx, dy) = self.delta();
 
-        a.abs() == b.abs()
+        dx != 0 && dx.abs() == dy.abs()
     }
 
-    pub fn to_int_points(&self) -> Vec<(i32, i32)> {
-        if self.is_horizontal() {
-            let y = self.start.1;
-            let x_start = self.start.0;
-            let x_end = self.end.0;
+    /// Zero-length segment, which covers a single point
+    pub fn is_point(&self) -> bool {
+        self.start == self.end
+    }
 
-            let (x_start, x_end) = (x_start.min(x_end), x_start.max(x_end));
+    pub fn endpoints(&self) -> [(i32, i32); 2] {
+        [self.start, self.end]
+    }
 
-            (x_start..=x_end).map(|x| (x, y)).collect()
-        } else if self.is_vertical() {
-            let x = self.start.0;
-            let y_start = self.start.1;
-            let y_end = self.end.1;
+    fn delta(&self) -> (i64, i64) {
+        (
+            i64::from(self.end.0) - i64::from(self.start.0),
+            i64::from(self.end.1) - i64::from(self.start.1),
+        )
+    }
 
-            let (y_start, y_end) = (y_start.min(y_end), y_start.max(y_end));
+    /// Number of steps between neighbouring integer points from start to end
+    fn n_steps(&self) -> i64 {
+        let (dx, dy) = self.delta();
 
-            (y_start..=y_end).map(|y| (x, y)).collect()
-        } else if self.is_diagonal() {
-            let n_steps = (self.start.0 - self.end.0).abs() as i32;
+        gcd(dx, dy)
+    }
 
-            let x_diff = (self.end.0 - self.start.0) as i32 / n_steps;
-            let y_diff = (self.end.1 - self.start.1) as i32 / n_steps;
+    /// Step between neighbouring integer points of the segment, directed so that
+    /// it goes left to right (or bottom to top for vertical lines).
+    ///
+    /// Single points are treated as horizontal segments.
+    pub fn step(&self) -> (i64, i64) {
+        let n_steps = self.n_steps();
+        if n_steps == 0 {
+            return (1, 0);
+        }
 
-            let mut x = self.start.0 as i32;
-            let mut y = self.start.1 as i32;
+        let (dx, dy) = self.delta();
+        let (dx, dy) = (dx / n_steps, dy / n_steps);
 
-            let mut points = Vec::with_capacity(n_steps as usize);
+        if dx < 0 || (dx == 0 && dy < 0) {
+            (-dx, -dy)
+        } else {
+            (dx, dy)
+        }
+    }
 
-            for _ in 0..=n_steps {
-                points.push((x, y));
-                x += x_diff;
-                y += y_diff;
-            }
+    /// Single points are considered horizontal, same as in `step`
+    pub fn orientation(&self) -> Orientation {
+        Orientation::of_step(self.step())
+    }
 
-            points
-        } else {
-            unreachable!()
+    /// All integer points of the segment, from start to end
+    pub fn to_int_points(&self) -> Vec<(i32, i32)> {
+        let n_steps = self.n_steps();
+        if n_steps == 0 {
+            return vec![self.start];
         }
+
+        let (dx, dy) = self.delta();
+        let (dx, dy) = (dx / n_steps, dy / n_steps);
+        let (x, y) = (i64::from(self.start.0), i64::from(self.start.1));
+
+        // all points lie between two i32 endpoints, so they fit into i32 as well
+        (0..=n_steps)
+            .map(|i| ((x + i * dx) as i32, (y + i * dy) as i32))
+            .collect()
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+
+    a
 }
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{index::VentIndex, line::LineSegment};
+
+pub(crate) type Step = (i64, i64);
+pub(crate) type Point = (i64, i64);
+
+/// Segment, projected onto its line `dy·x - dx·y = key`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Interval {
+    pub step: Step,
+    pub key: i128,
+    pub lo: i64,
+    pub hi: i64,
+}
+
+/// Part of a line, covered by the same number of segments
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Run {
+    pub step: Step,
+    pub key: i128,
+    pub lo: i64,
+    pub hi: i64,
+    pub count: u32,
+}
+
+/// Position of a point along the line with given step
+pub(crate) fn param(step: Step, (x, y): Point) -> i64 {
+    if step.0 != 0 {
+        x
+    } else {
+        y
+    }
+}
+
+/// Difference in `param` between neighbouring points on the line
+fn stride(step: Step) -> i64 {
+    if step.0 != 0 {
+        step.0
+    } else {
+        step.1
+    }
+}
+
+pub(crate) fn key(step: Step, (x, y): Point) -> i128 {
+    i128::from(step.1) * i128::from(x) - i128::from(step.0) * i128::from(y)
+}
+
+/// Range of `i`, for which `start + i·delta` lies within `min..=max`, computed in i128,
+/// so that unbounded rectangles don't overflow
+fn index_range(start: i64, delta: i64, min: i64, max: i64) -> Option<(i128, i128)> {
+    if delta == 0 {
+        return (min..=max)
+            .contains(&start)
+            .then_some((i128::MIN, i128::MAX));
+    }
+
+    let (mut from, mut to, mut delta) = (
+        i128::from(min) - i128::from(start),
+        i128::from(max) - i128::from(start),
+        i128::from(delta),
+    );
+    if delta < 0 {
+        (from, to, delta) = (-to, -from, -delta);
+    }
+
+    Some((-(-from).div_euclid(delta), to.div_euclid(delta)))
+}
+
+impl Interval {
+    pub fn new(line: &LineSegment) -> Self {
+        let step = line.step();
+        let [start, end] = line.endpoints().map(|(x, y)| (i64::from(x), i64::from(y)));
+
+        let (a, b) = (param(step, start), param(step, end));
+
+        Self {
+            step,
+            key: key(step, start),
+            lo: a.min(b),
+            hi: a.max(b),
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        key(self.step, point) == self.key && (self.lo..=self.hi).contains(&param(self.step, point))
+    }
+}
+
+impl Run {
+    pub fn n_points(&self) -> usize {
+        ((self.hi - self.lo) / stride(self.step) + 1) as usize
+    }
+
+    fn point_at(&self, t: i64) -> Point {
+        let (dx, dy) = self.step;
+
+        if dx != 0 {
+            let y = (i128::from(dy) * i128::from(t) - self.key) / i128::from(dx);
+
+            (t, y as i64)
+        } else {
+            (self.key as i64, t)
+        }
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (x, y) = self.point_at(self.lo);
+        let (dx, dy) = self.step;
+
+        (0..self.n_points() as i64).map(move |i| (x + i * dx, y + i * dy))
+    }
+
+    /// Number of points of the run within rectangle `min..=max`
+    pub fn n_points_within(&self, min: Point, max: Point) -> usize {
+        let (x, y) = self.point_at(self.lo);
+        let (dx, dy) = self.step;
+
+        let ranges = index_range(x, dx, min.0, max.0).zip(index_range(y, dy, min.1, max.1));
+
+        match ranges {
+            Some(((x_lo, x_hi), (y_lo, y_hi))) => {
+                let lo = x_lo.max(y_lo).max(0);
+                let hi = x_hi.min(y_hi).min(self.n_points() as i128 - 1);
+
+                (hi - lo + 1).max(0) as usize
+            }
+            None => 0,
+        }
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        let t = param(self.step, point);
+
+        key(self.step, point) == self.key
+            && (self.lo..=self.hi).contains(&t)
+            && (t - self.lo) % stride(self.step) == 0
+    }
+
+    /// Integer point, where two runs with different steps cross
+    pub fn crossing(&self, other: &Self) -> Option<Point> {
+        let (dx1, dy1) = (i128::from(self.step.0), i128::from(self.step.1));
+        let (dx2, dy2) = (i128::from(other.step.0), i128::from(other.step.1));
+        let (k1, k2) = (self.key, other.key);
+
+        let det = dx1 * dy2 - dy1 * dx2;
+        if det == 0 {
+            return None;
+        }
+
+        let x_num = dx1 * k2 - dx2 * k1;
+        let y_num = dy1 * k2 - dy2 * k1;
+        if x_num % det != 0 || y_num % det != 0 {
+            return None;
+        }
+
+        let point = (
+            i64::try_from(x_num / det).ok()?,
+            i64::try_from(y_num / det).ok()?,
+        );
+
+        (self.contains(point) && other.contains(point)).then_some(point)
+    }
+}
+
+/// Range of keys of the lines with `step`, passing through points of the run
+fn key_range(run: &Run, step: Step) -> (i128, i128) {
+    let (a, b) = (
+        key(step, run.point_at(run.lo)),
+        key(step, run.point_at(run.hi)),
+    );
+
+    (a.min(b), a.max(b))
+}
+
+/// Point, where runs of different directions cross
+#[derive(Debug)]
+pub(crate) struct Crossing {
+    pub point: Point,
+    /// indices of all runs, passing through the point
+    pub runs: Vec<usize>,
+}
+
+/// All integer points, where runs with different steps cross, sorted by point.
+///
+/// For each pair of steps the plane is mapped onto keys of both steps, where runs of
+/// the first step become vertical segments and runs of the second one horizontal ones.
+/// A sweep over the first key finds all segments, which meet, in O((n + k)·log n) time
+/// for n runs and k meeting pairs, and only those are checked for an integer crossing.
+pub(crate) fn find_crossings(runs: &[Run]) -> Vec<Crossing> {
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    enum Event {
+        Insert,
+        Query,
+        Remove,
+    }
+
+    let mut by_step: BTreeMap<Step, Vec<usize>> = BTreeMap::new();
+    for (i, run) in runs.iter().enumerate() {
+        by_step.entry(run.step).or_default().push(i);
+    }
+    let by_step = by_step.into_iter().collect::<Vec<_>>();
+
+    let mut crossing_runs = Vec::new();
+    let mut events = Vec::new();
+    let mut active = BTreeSet::new();
+
+    for (a, (first_step, first)) in by_step.iter().enumerate() {
+        for (second_step, second) in &by_step[a + 1..] {
+            events.clear();
+            for &j in second {
+                let (lo, hi) = key_range(&runs[j], *first_step);
+                events.push((lo, Event::Insert, j));
+                events.push((hi, Event::Remove, j));
+            }
+            for &i in first {
+                events.push((runs[i].key, Event::Query, i));
+            }
+            events.sort_unstable();
+
+            for (_, event, run) in &events {
+                match event {
+                    Event::Insert => {
+                        active.insert((runs[*run].key, *run));
+                    }
+                    Event::Remove => {
+                        active.remove(&(runs[*run].key, *run));
+                    }
+                    Event::Query => {
+                        let (lo, hi) = key_range(&runs[*run], *second_step);
+
+                        for &(_, other) in active.range((lo, 0)..=(hi, usize::MAX)) {
+                            if let Some(point) = runs[*run].crossing(&runs[other]) {
+                                crossing_runs.push((point, *run));
+                                crossing_runs.push((point, other));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // several pairs of runs may cross at the same point
+    crossing_runs.sort_unstable();
+    crossing_runs.dedup();
+
+    crossing_runs
+        .chunk_by(|a, b| a.0 == b.0)
+        .map(|group| Crossing {
+            point: group[0].0,
+            runs: group.iter().map(|&(_, run)| run).collect(),
+        })
+        .collect()
+}
+
+/// Merge collinear intervals into runs of constant coverage
+pub(crate) fn build_runs(mut intervals: Vec<Interval>) -> Vec<Run> {
+    intervals.sort_unstable();
+
+    let mut runs = Vec::new();
+    let mut events = Vec::new();
+
+    for group in intervals.chunk_by(|a, b| a.step == b.step && a.key == b.key) {
+        let (step, key) = (group[0].step, group[0].key);
+        let stride = stride(step);
+
+        events.clear();
+        for interval in group {
+            events.push((interval.lo, 1i32));
+            events.push((interval.hi + stride, -1));
+        }
+        events.sort_unstable();
+
+        let mut count = 0;
+        let mut from = events[0].0;
+
+        for &(position, delta) in &events {
+            if position != from && count > 0 {
+                runs.push(Run {
+                    step,
+                    key,
+                    lo: from,
+                    hi: position - stride,
+                    count: count as u32,
+                });
+            }
+
+            from = position;
+            count += delta;
+        }
+    }
+
+    runs
+}
+
+/// Number of integer points, covered by at least `min_overlap` lines
+pub fn count_overlaps<'a, I>(lines: I, min_overlap: u32) -> usize
+where
+    I: IntoIterator<Item = &'a LineSegment>,
+{
+    VentIndex::new(lines).count_overlaps(min_overlap)
+}
@@ -1,6 +1,13 @@
-use std::{error::Error, str::FromStr};
+use std::{error::Error, num::NonZeroU64, str::FromStr};
 
 use advent_utils::{Part, Solver};
+use num_bigint::BigUint;
+
+pub use matrix::{Arithmetic, Exact, Matrix, Modulo, Overflow, Saturating};
+pub use model::{LanternfishModel, ModelError, PopulationSeries};
+
+mod matrix;
+mod model;
 
 const PART_ONE_MOVES: usize = 80;
 const PART_TWO_MOVES: usize = 256;
@@ -8,61 +15,101 @@ const PART_TWO_MOVES: usize = 256;
 #[derive(Debug)]
 pub struct Solution {
     lanternfish_timers: Vec<u64>,
+    model: LanternfishModel,
+    initial_state: Vec<u64>,
 }
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(input_data: &str) -> Result<Self, Self::Err> {
-        let lanternfish_timers = input_data
+        let lanternfish_timers: Vec<u64> = input_data
             .trim_end()
             .split(',')
             .map(str::parse)
             .collect::<Result<_, _>>()?;
 
-        Ok(Self { lanternfish_timers })
+        let model = LanternfishModel::default();
+        let initial_state = model.initial_state(&lanternfish_timers)?;
+
+        Ok(Self {
+            lanternfish_timers,
+            model,
+            initial_state,
+        })
     }
 }
 
-fn simulate_lanternfish_mating(timers: &[u64], moves: usize) -> [u64; 9] {
-    let mut state = [0; 9];
+impl Solution {
+    /// Replace default lifecycle parameters
+    pub fn with_model(mut self, model: LanternfishModel) -> Result<Self, ModelError> {
+        self.initial_state = model.initial_state(&self.lanternfish_timers)?;
+        self.model = model;
 
-    for &time in timers {
-        state[time as usize] += 1;
+        Ok(self)
     }
 
-    for _ in 0..moves {
-        let mut new_state = [0; 9];
-        new_state[..8].copy_from_slice(&state[1..=8]);
+    /// Population on each day from 0 to `days`, broken down by timer value
+    pub fn series(&self, days: usize) -> Result<PopulationSeries, ModelError> {
+        self.model.series(&self.lanternfish_timers, days)
+    }
 
-        new_state[8] = state[0];
-        new_state[6] += state[0];
+    /// Population after given number of days, or error, if it doesn't fit into u128
+    /// (`u128::MAX` itself is reported as an overflow)
+    pub fn population_after(&self, days: u64) -> Result<u128, Overflow> {
+        let population = match self
+            .model
+            .population_after(&Saturating, &self.initial_state, days)
+        {
+            Ok(population) => population,
+            Err(infallible) => match infallible {},
+        };
+
+        // intermediate matrix elements may saturate, while the result still fits
+        if population == u128::MAX {
+            Err(Overflow)
+        } else {
+            Ok(population)
+        }
+    }
 
-        state = new_state;
+    /// Population after given number of days, modulo `modulus`
+    pub fn population_after_mod(&self, days: u64, modulus: NonZeroU64) -> u64 {
+        match self
+            .model
+            .population_after(&Modulo(modulus), &self.initial_state, days)
+        {
+            Ok(population) => population,
+            Err(infallible) => match infallible {},
+        }
     }
 
-    state
+    /// Exact population after given number of days
+    pub fn population_after_exact(&self, days: u64) -> BigUint {
+        match self
+            .model
+            .population_after(&Exact, &self.initial_state, days)
+        {
+            Ok(population) => population,
+            Err(infallible) => match infallible {},
+        }
+    }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
-        match part {
-            Part::One => {
-                let population_size: u64 =
-                    simulate_lanternfish_mating(&self.lanternfish_timers, PART_ONE_MOVES)
-                        .into_iter()
-                        .sum();
-
-                format!("population size after 80 moves is {}", population_size)
-            }
-            Part::Two => {
-                let population_size: u64 =
-                    simulate_lanternfish_mating(&self.lanternfish_timers, PART_TWO_MOVES)
-                        .into_iter()
-                        .sum();
-
-                format!("population size after 256 moves is {}", population_size)
-            }
+        let moves = match part {
+            Part::One => PART_ONE_MOVES,
+            Part::Two => PART_TWO_MOVES,
+        };
+
+        match self.series(moves) {
+            Ok(series) => format!(
+                "population size after {} moves is {}",
+                moves,
+                series.last_total()
+            ),
+            Err(e) => format!("failed to simulate population: {}", e),
         }
     }
 
@@ -75,27 +122,110 @@ impl Solver for Solution {
 mod tests {
     use super::*;
 
+    fn example() -> Solution {
+        "3,4,3,1,2".parse().unwrap()
+    }
+
     #[test]
     fn test_part_one_example() {
-        let input = [3, 4, 3, 1, 2];
+        assert_eq!(example().series(PART_ONE_MOVES).unwrap().last_total(), 5934);
+    }
 
+    #[test]
+    fn test_part_two_example() {
         assert_eq!(
-            simulate_lanternfish_mating(&input, PART_ONE_MOVES)
-                .into_iter()
-                .sum::<u64>(),
-            5934,
+            example().series(PART_TWO_MOVES).unwrap().last_total(),
+            26984457539
         );
     }
 
     #[test]
-    fn test_part_two_example() {
-        let input = [3, 4, 3, 1, 2];
+    fn test_matrix_exponentiation() {
+        let solution = example();
+        let series = solution.series(400).unwrap();
+
+        for days in [0, 1, 18, PART_ONE_MOVES, PART_TWO_MOVES, 400] {
+            let simulated = u128::from(series.totals()[days]);
+
+            assert_eq!(solution.population_after(days as u64), Ok(simulated));
+            assert_eq!(
+                solution.population_after_exact(days as u64),
+                BigUint::from(simulated)
+            );
+            assert_eq!(
+                u128::from(
+                    solution
+                        .population_after_mod(days as u64, NonZeroU64::new(1_000_000_007).unwrap())
+                ),
+                simulated % 1_000_000_007
+            );
+        }
+
+        assert_eq!(solution.population_after(2000), Err(Overflow));
+        assert_eq!(solution.population_after(1_000_000_000_000), Err(Overflow));
+        assert_eq!(
+            BigUint::from(
+                solution.population_after_mod(2000, NonZeroU64::new(998_244_353).unwrap())
+            ),
+            solution.population_after_exact(2000) % 998_244_353u32
+        );
+        assert!(matches!(
+            solution.series(2000),
+            Err(ModelError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_overflow_boundary() {
+        let solution = example();
+
+        // matrix elements overflow before the population does, only the latter counts
+        for days in 900..1100 {
+            let exact = solution.population_after_exact(days);
+            assert_eq!(
+                solution.population_after(days),
+                u128::try_from(exact).map_err(|_| Overflow)
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_model() {
+        // fish reproduce every 3 days, newborns need 5 days for the first child
+        let model = LanternfishModel::new(2, 4);
+        let solution = example().with_model(model).unwrap();
+
+        let series = solution.series(30).unwrap();
+        for days in [0, 5, 17, 30] {
+            assert_eq!(
+                solution.population_after(days as u64),
+                Ok(u128::from(series.totals()[days]))
+            );
+        }
+
+        assert_eq!(series.state(0), Some(&[0, 1, 1, 2, 1][..]));
+        assert_eq!(series.state(1), Some(&[1, 1, 2, 1, 0][..]));
+        assert_eq!(series.state(2), Some(&[1, 2, 2, 0, 1][..]));
+
+        assert_eq!(
+            example()
+                .with_model(LanternfishModel::new(2, 3))
+                .unwrap_err(),
+            ModelError::TimerOutOfRange { timer: 4, max: 3 }
+        );
+        assert!("3,9,1".parse::<Solution>().is_err());
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let csv = example().series(2).unwrap().to_csv();
 
         assert_eq!(
-            simulate_lanternfish_mating(&input, PART_TWO_MOVES)
-                .into_iter()
-                .sum::<u64>(),
-            26984457539,
+            csv,
+            "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8\n\
+             0,5,0,1,1,2,1,0,0,0,0\n\
+             1,5,1,1,2,1,0,0,0,0,0\n\
+             2,6,1,2,1,0,0,0,1,0,1\n"
         );
     }
 }
@@ -0,0 +1,217 @@
+use std::num::NonZeroU64;
+
+use displaydoc::Display;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+/// Population count doesn't fit into u128
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub struct Overflow;
+
+/// Arithmetic, used for matrix elements
+pub trait Arithmetic {
+    type Value: Clone;
+    type Error;
+
+    fn zero(&self) -> Self::Value;
+    fn one(&self) -> Self::Value;
+    fn value_of(&self, n: u64) -> Self::Value;
+    fn add(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, Self::Error>;
+    fn mul(&self, a: &Self::Value, b: &Self::Value) -> Result<Self::Value, Self::Error>;
+}
+
+/// u128 arithmetic, saturating at `u128::MAX`
+///
+/// As all values are non-negative, a saturated value only contributes to saturated
+/// results, so any result below `u128::MAX` is exact
+#[derive(Debug, Clone, Copy)]
+pub struct Saturating;
+
+/// Arithmetic modulo given number
+#[derive(Debug, Clone, Copy)]
+pub struct Modulo(pub NonZeroU64);
+
+/// Arbitrary-precision arithmetic
+#[derive(Debug, Clone, Copy)]
+pub struct Exact;
+
+impl Arithmetic for Saturating {
+    type Value = u128;
+    type Error = std::convert::Infallible;
+
+    fn zero(&self) -> u128 {
+        0
+    }
+
+    fn one(&self) -> u128 {
+        1
+    }
+
+    fn value_of(&self, n: u64) -> u128 {
+        u128::from(n)
+    }
+
+    fn add(&self, a: &u128, b: &u128) -> Result<u128, Self::Error> {
+        Ok(a.saturating_add(*b))
+    }
+
+    fn mul(&self, a: &u128, b: &u128) -> Result<u128, Self::Error> {
+        Ok(a.saturating_mul(*b))
+    }
+}
+
+impl Arithmetic for Modulo {
+    type Value = u64;
+    type Error = std::convert::Infallible;
+
+    fn zero(&self) -> u64 {
+        0
+    }
+
+    fn one(&self) -> u64 {
+        1 % self.0
+    }
+
+    fn value_of(&self, n: u64) -> u64 {
+        n % self.0
+    }
+
+    fn add(&self, a: &u64, b: &u64) -> Result<u64, Self::Error> {
+        Ok(((u128::from(*a) + u128::from(*b)) % u128::from(self.0.get())) as u64)
+    }
+
+    fn mul(&self, a: &u64, b: &u64) -> Result<u64, Self::Error> {
+        Ok(((u128::from(*a) * u128::from(*b)) % u128::from(self.0.get())) as u64)
+    }
+}
+
+impl Arithmetic for Exact {
+    type Value = BigUint;
+    type Error = std::convert::Infallible;
+
+    fn zero(&self) -> BigUint {
+        BigUint::from(0u32)
+    }
+
+    fn one(&self) -> BigUint {
+        BigUint::from(1u32)
+    }
+
+    fn value_of(&self, n: u64) -> BigUint {
+        BigUint::from(n)
+    }
+
+    fn add(&self, a: &BigUint, b: &BigUint) -> Result<BigUint, Self::Error> {
+        Ok(a + b)
+    }
+
+    fn mul(&self, a: &BigUint, b: &BigUint) -> Result<BigUint, Self::Error> {
+        Ok(a * b)
+    }
+}
+
+/// Square matrix, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix<T> {
+    size: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Matrix<T> {
+    pub fn identity<A: Arithmetic<Value = T>>(arithmetic: &A, size: usize) -> Self {
+        let mut cells = vec![arithmetic.zero(); size * size];
+        for i in 0..size {
+            cells[i * size + i] = arithmetic.one();
+        }
+
+        Self { size, cells }
+    }
+
+    /// Build matrix from `(row, col)` positions of ones, all other cells are zeroes
+    pub fn from_ones<A: Arithmetic<Value = T>>(
+        arithmetic: &A,
+        size: usize,
+        ones: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Result<Self, A::Error> {
+        let mut cells = vec![arithmetic.zero(); size * size];
+        for (row, col) in ones {
+            let cell = &mut cells[row * size + col];
+            *cell = arithmetic.add(cell, &arithmetic.one())?;
+        }
+
+        Ok(Self { size, cells })
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> &T {
+        &self.cells[row * self.size + col]
+    }
+
+    pub fn mul<A: Arithmetic<Value = T>>(
+        &self,
+        arithmetic: &A,
+        other: &Self,
+    ) -> Result<Self, A::Error> {
+        debug_assert_eq!(self.size, other.size);
+
+        let size = self.size;
+        let mut cells = Vec::with_capacity(size * size);
+
+        for row in 0..size {
+            for col in 0..size {
+                let mut sum = arithmetic.zero();
+                for k in 0..size {
+                    let product = arithmetic.mul(self.get(row, k), other.get(k, col))?;
+                    sum = arithmetic.add(&sum, &product)?;
+                }
+
+                cells.push(sum);
+            }
+        }
+
+        Ok(Self { size, cells })
+    }
+
+    /// Raise matrix to the given power, using O(log power) multiplications
+    pub fn pow<A: Arithmetic<Value = T>>(
+        &self,
+        arithmetic: &A,
+        mut power: u64,
+    ) -> Result<Self, A::Error> {
+        let mut result = Self::identity(arithmetic, self.size);
+        let mut base = self.clone();
+
+        while power > 0 {
+            if power & 1 == 1 {
+                result = result.mul(arithmetic, &base)?;
+            }
+
+            power >>= 1;
+            if power > 0 {
+                base = base.mul(arithmetic, &base)?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Multiply matrix by a column vector
+    pub fn apply<A: Arithmetic<Value = T>>(
+        &self,
+        arithmetic: &A,
+        vector: &[T],
+    ) -> Result<Vec<T>, A::Error> {
+        debug_assert_eq!(self.size, vector.len());
+
+        (0..self.size)
+            .map(|row| {
+                vector
+                    .iter()
+                    .enumerate()
+                    .try_fold(arithmetic.zero(), |sum, (col, value)| {
+                        let product = arithmetic.mul(self.get(row, col), value)?;
+                        arithmetic.add(&sum, &product)
+                    })
+            })
+            .collect()
+    }
+}
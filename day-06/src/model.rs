@@ -0,0 +1,179 @@
+use std::fmt::Write;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::matrix::{Arithmetic, Matrix};
+
+/// Lifecycle parameters of lanternfish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LanternfishModel {
+    /// timer value of a fish after it gave birth
+    reset_timer: usize,
+    /// timer value of a newborn fish
+    newborn_timer: usize,
+}
+
+/// Lanternfish model error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ModelError {
+    /// Timer value {timer} is out of model range 0..={max}
+    TimerOutOfRange { timer: u64, max: usize },
+    /// Population count overflowed on day {day}
+    Overflow { day: usize },
+}
+
+impl Default for LanternfishModel {
+    fn default() -> Self {
+        Self::new(6, 8)
+    }
+}
+
+impl LanternfishModel {
+    pub fn new(reset_timer: usize, newborn_timer: usize) -> Self {
+        Self {
+            reset_timer,
+            newborn_timer,
+        }
+    }
+
+    /// Number of distinct timer values
+    pub fn n_buckets(&self) -> usize {
+        self.reset_timer.max(self.newborn_timer) + 1
+    }
+
+    /// Counts of fish by timer value
+    pub fn initial_state(&self, timers: &[u64]) -> Result<Vec<u64>, ModelError> {
+        let mut state = vec![0; self.n_buckets()];
+
+        for &timer in timers {
+            let count = usize::try_from(timer)
+                .ok()
+                .and_then(|timer| state.get_mut(timer))
+                .ok_or(ModelError::TimerOutOfRange {
+                    timer,
+                    max: self.n_buckets() - 1,
+                })?;
+
+            *count += 1;
+        }
+
+        Ok(state)
+    }
+
+    /// State on the next day, `None` on overflow
+    pub fn step(&self, state: &[u64]) -> Option<Vec<u64>> {
+        let mut new_state = state[1..].to_vec();
+        new_state.push(0);
+
+        new_state[self.reset_timer] = new_state[self.reset_timer].checked_add(state[0])?;
+        new_state[self.newborn_timer] = new_state[self.newborn_timer].checked_add(state[0])?;
+
+        Some(new_state)
+    }
+
+    /// State of the population on each day from 0 to `days` inclusive
+    pub fn series(&self, timers: &[u64], days: usize) -> Result<PopulationSeries, ModelError> {
+        let total = |state: &[u64], day| {
+            state
+                .iter()
+                .try_fold(0u64, |total, &count| total.checked_add(count))
+                .ok_or(ModelError::Overflow { day })
+        };
+
+        let initial_state = self.initial_state(timers)?;
+        let mut totals = vec![total(&initial_state, 0)?];
+        let mut states = vec![initial_state];
+
+        for day in 1..=days {
+            let state = self
+                .step(&states[day - 1])
+                .ok_or(ModelError::Overflow { day })?;
+
+            totals.push(total(&state, day)?);
+            states.push(state);
+        }
+
+        Ok(PopulationSeries { states, totals })
+    }
+
+    /// Matrix, which turns counts of fish by timer value into counts on the next day
+    pub fn transition_matrix<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+    ) -> Result<Matrix<A::Value>, A::Error> {
+        let ones = (0..self.n_buckets() - 1)
+            .map(|timer| (timer, timer + 1))
+            .chain([(self.reset_timer, 0), (self.newborn_timer, 0)]);
+
+        Matrix::from_ones(arithmetic, self.n_buckets(), ones)
+    }
+
+    /// Total population after given number of days, starting from `initial_state`,
+    /// computed in O(log days)
+    pub fn population_after<A: Arithmetic>(
+        &self,
+        arithmetic: &A,
+        initial_state: &[u64],
+        days: u64,
+    ) -> Result<A::Value, A::Error> {
+        debug_assert_eq!(initial_state.len(), self.n_buckets());
+
+        let state = initial_state
+            .iter()
+            .map(|&count| arithmetic.value_of(count))
+            .collect::<Vec<_>>();
+        let state = self
+            .transition_matrix(arithmetic)?
+            .pow(arithmetic, days)?
+            .apply(arithmetic, &state)?;
+
+        state
+            .iter()
+            .try_fold(arithmetic.zero(), |sum, count| arithmetic.add(&sum, count))
+    }
+}
+
+/// Per-day population, broken down by timer value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PopulationSeries {
+    states: Vec<Vec<u64>>,
+    totals: Vec<u64>,
+}
+
+impl PopulationSeries {
+    /// Counts of fish by timer value on the given day
+    pub fn state(&self, day: usize) -> Option<&[u64]> {
+        self.states.get(day).map(Vec::as_slice)
+    }
+
+    /// Total population for each day, starting from day 0
+    pub fn totals(&self) -> &[u64] {
+        &self.totals
+    }
+
+    pub fn last_total(&self) -> u64 {
+        self.totals.last().copied().unwrap_or(0)
+    }
+
+    /// CSV with a row per day: day, total population and count for each timer value
+    pub fn to_csv(&self) -> String {
+        let n_buckets = self.states.first().map_or(0, Vec::len);
+        let mut csv = String::from("day,total");
+
+        for timer in 0..n_buckets {
+            write!(csv, ",timer_{}", timer).expect("writing to string never fails");
+        }
+        csv.push('\n');
+
+        for (day, (state, total)) in self.states.iter().zip(&self.totals).enumerate() {
+            write!(csv, "{},{}", day, total).expect("writing to string never fails");
+            for count in state {
+                write!(csv, ",{}", count).expect("writing to string never fails");
+            }
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
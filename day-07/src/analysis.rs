@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use crate::cost::{Alignment, CostFunction, Crabs};
+
+/// Total fuel usage for each position in a range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuelCurve {
+    first_position: i64,
+    fuel: Vec<i64>,
+}
+
+impl FuelCurve {
+    pub fn new<C: CostFunction + ?Sized>(
+        cost: &C,
+        crabs: &Crabs,
+        first_position: i64,
+        last_position: i64,
+    ) -> Self {
+        let fuel = (first_position..=last_position)
+            .map(|position| cost.total(crabs, position))
+            .collect();
+
+        Self {
+            first_position,
+            fuel,
+        }
+    }
+
+    /// `(position, fuel)` pairs from the leftmost position
+    pub fn points(&self) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (self.first_position..).zip(self.fuel.iter().copied())
+    }
+
+    pub fn fuel_at(&self, position: i64) -> Option<i64> {
+        let i = usize::try_from(position.checked_sub(self.first_position)?).ok()?;
+
+        self.fuel.get(i).copied()
+    }
+
+    pub fn min_fuel(&self) -> Option<i64> {
+        self.fuel.iter().copied().min()
+    }
+
+    /// All positions of the curve with the minimal fuel usage
+    pub fn min_positions(&self) -> Vec<i64> {
+        let min_fuel = self.min_fuel();
+
+        self.points()
+            .filter(|&(_, fuel)| Some(fuel) == min_fuel)
+            .map(|(position, _)| position)
+            .collect()
+    }
+}
+
+/// CSV with `position` column and a fuel column per each named curve
+///
+/// Curves are expected to cover the same positions, rows are emitted
+/// while all of them have points.
+pub fn curves_to_csv(curves: &[(&str, &FuelCurve)]) -> String {
+    let mut csv = String::from("position");
+    for (name, _) in curves {
+        write!(csv, ",{}", name).expect("writing to string never fails");
+    }
+    csv.push('\n');
+
+    let mut points = curves
+        .iter()
+        .map(|(_, curve)| curve.points())
+        .collect::<Vec<_>>();
+
+    'rows: loop {
+        let mut row = Vec::with_capacity(points.len());
+        for curve_points in &mut points {
+            match curve_points.next() {
+                Some(point) => row.push(point),
+                None => break 'rows,
+            }
+        }
+
+        let Some(&(position, _)) = row.first() else {
+            break;
+        };
+
+        write!(csv, "{}", position).expect("writing to string never fails");
+        for (_, fuel) in row {
+            write!(csv, ",{}", fuel).expect("writing to string never fails");
+        }
+        csv.push('\n');
+    }
+
+    csv
+}
+
+/// Crabs, split into two groups with their own meeting points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MeetingPoints {
+    /// meeting point of the leftmost crabs
+    pub left: Alignment,
+    /// meeting point of all the other crabs
+    pub right: Alignment,
+    /// number of crabs in the left group
+    pub left_size: usize,
+}
+
+impl MeetingPoints {
+    pub fn fuel(&self) -> i64 {
+        self.left.fuel + self.right.fuel
+    }
+}
+
+/// Cheapest pair of meeting points, `None` if there are less than two crabs
+///
+/// For a convex cost each crab prefers the nearest meeting point, so optimal
+/// groups are contiguous in sorted order and it's enough to try every split.
+pub fn best_meeting_points<C: CostFunction + ?Sized>(
+    cost: &C,
+    crabs: &Crabs,
+) -> Option<MeetingPoints> {
+    (1..crabs.len())
+        .filter_map(|left_size| {
+            let (left, right) = crabs.split_at(left_size);
+
+            Some(MeetingPoints {
+                left: cost.optimum(&left)?,
+                right: cost.optimum(&right)?,
+                left_size,
+            })
+        })
+        .min_by_key(|meeting_points| meeting_points.fuel())
+}
@@ -0,0 +1,252 @@
+use std::{ops::RangeInclusive, rc::Rc};
+
+#[derive(Debug)]
+struct Positions {
+    sorted: Vec<i64>,
+    /// `prefix[i]` is the sum of first `i` sorted positions
+    prefix: Vec<i64>,
+    /// `square_prefix[i]` is the sum of squares of first `i` sorted positions
+    square_prefix: Vec<i64>,
+}
+
+/// Crab positions, sorted and prepared for fast fuel computations
+///
+/// Cheaply splits into groups of crabs, sharing the same underlying data.
+#[derive(Debug, Clone)]
+pub struct Crabs {
+    positions: Rc<Positions>,
+    /// part of sorted positions, belonging to this group
+    from: usize,
+    to: usize,
+}
+
+/// Position to align crabs at, with fuel needed to get there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub fuel: i64,
+}
+
+impl Crabs {
+    pub fn new(positions: &[i64]) -> Self {
+        let mut sorted = positions.to_vec();
+        sorted.sort_unstable();
+
+        let mut prefix = Vec::with_capacity(sorted.len() + 1);
+        let mut square_prefix = Vec::with_capacity(sorted.len() + 1);
+        let (mut sum, mut square_sum) = (0, 0);
+
+        prefix.push(sum);
+        square_prefix.push(square_sum);
+        for &pos in &sorted {
+            sum += pos;
+            square_sum += pos * pos;
+
+            prefix.push(sum);
+            square_prefix.push(square_sum);
+        }
+
+        Self {
+            from: 0,
+            to: sorted.len(),
+            positions: Rc::new(Positions {
+                sorted,
+                prefix,
+                square_prefix,
+            }),
+        }
+    }
+
+    /// Split into `k` leftmost crabs and all the rest
+    pub fn split_at(&self, k: usize) -> (Self, Self) {
+        assert!(k <= self.len(), "can't split {} crabs at {}", self.len(), k);
+
+        let mid = self.from + k;
+
+        (
+            Self {
+                to: mid,
+                ..self.clone()
+            },
+            Self {
+                from: mid,
+                ..self.clone()
+            },
+        )
+    }
+
+    pub fn sorted(&self) -> &[i64] {
+        &self.positions.sorted[self.from..self.to]
+    }
+
+    pub fn len(&self) -> usize {
+        self.to - self.from
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn min_pos(&self) -> Option<i64> {
+        self.sorted().first().copied()
+    }
+
+    pub fn max_pos(&self) -> Option<i64> {
+        self.sorted().last().copied()
+    }
+
+    /// Sum of positions of crabs in `from..to` (indices in the group)
+    fn sum(&self, from: usize, to: usize) -> i64 {
+        self.positions.prefix[self.from + to] - self.positions.prefix[self.from + from]
+    }
+
+    /// Sum of all positions
+    pub fn position_sum(&self) -> i64 {
+        self.sum(0, self.len())
+    }
+
+    /// Sum of `|pos - position|` over all crabs, in O(log n)
+    pub fn distance_sum(&self, position: i64) -> i64 {
+        let n = self.len();
+        let left = self.sorted().partition_point(|&pos| pos < position);
+        let (n_left, n_right) = (left as i64, (n - left) as i64);
+
+        (position * n_left - self.sum(0, left)) + (self.sum(left, n) - position * n_right)
+    }
+
+    /// Sum of `(pos - position)²` over all crabs, in O(1)
+    pub fn square_distance_sum(&self, position: i64) -> i64 {
+        let square_prefix = &self.positions.square_prefix;
+        let square_sum = square_prefix[self.to] - square_prefix[self.from];
+        let n = self.len() as i64;
+
+        square_sum - 2 * position * self.position_sum() + n * position * position
+    }
+}
+
+/// Fuel cost of moving a single crab
+///
+/// Cost must be convex in distance, so the total fuel is convex in position
+/// and its minimum can be found with ternary search.
+pub trait CostFunction {
+    /// Fuel, needed to move a crab by `distance` (non-negative) steps
+    fn cost(&self, distance: i64) -> i64;
+
+    /// Fuel, needed to align all crabs at the position
+    fn total(&self, crabs: &Crabs, position: i64) -> i64 {
+        crabs
+            .sorted()
+            .iter()
+            .map(|&pos| self.cost((pos - position).abs()))
+            .sum()
+    }
+
+    /// Cheapest position to align crabs at (the leftmost one, if there are several),
+    /// `None` if there are no crabs
+    fn optimum(&self, crabs: &Crabs) -> Option<Alignment> {
+        let (lo, hi) = (crabs.min_pos()?, crabs.max_pos()?);
+
+        Some(ternary_search(lo, hi, |position| {
+            self.total(crabs, position)
+        }))
+    }
+
+    /// All positions with the minimal fuel usage, `None` if there are no crabs
+    ///
+    /// Total fuel is convex, so positions tied for minimum are always contiguous.
+    fn optimal_positions(&self, crabs: &Crabs) -> Option<RangeInclusive<i64>> {
+        let Alignment { position, fuel } = self.optimum(crabs)?;
+
+        // last position to the right with the same fuel usage, up to the last crab
+        let (mut lo, mut hi) = (position, crabs.max_pos()?.max(position));
+        while lo < hi {
+            let mid = lo + (hi - lo + 1) / 2;
+
+            if self.total(crabs, mid) == fuel {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+
+        Some(position..=lo)
+    }
+}
+
+/// Every step costs one unit of fuel
+#[derive(Debug, Clone, Copy)]
+pub struct Linear;
+
+/// Every next step costs one unit more than the previous one
+#[derive(Debug, Clone, Copy)]
+pub struct Triangular;
+
+#[inline]
+pub fn sum_up_to_n(n: i64) -> i64 {
+    (n * (n + 1)) / 2
+}
+
+impl CostFunction for Linear {
+    fn cost(&self, distance: i64) -> i64 {
+        distance
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> i64 {
+        crabs.distance_sum(position)
+    }
+
+    fn optimum(&self, crabs: &Crabs) -> Option<Alignment> {
+        // any position between two middle crabs is optimal, the lower median is the leftmost one
+        let position = *crabs.sorted().get(crabs.len().checked_sub(1)? / 2)?;
+
+        Some(Alignment {
+            position,
+            fuel: self.total(crabs, position),
+        })
+    }
+}
+
+impl CostFunction for Triangular {
+    fn cost(&self, distance: i64) -> i64 {
+        sum_up_to_n(distance)
+    }
+
+    fn total(&self, crabs: &Crabs, position: i64) -> i64 {
+        // d·(d + 1) / 2 summed over all crabs
+        (crabs.square_distance_sum(position) + crabs.distance_sum(position)) / 2
+    }
+
+    fn optimum(&self, crabs: &Crabs) -> Option<Alignment> {
+        if crabs.is_empty() {
+            return None;
+        }
+
+        // optimum lies within 1/2 from the mean
+        let mean = crabs.position_sum().div_euclid(crabs.len() as i64);
+
+        Some(ternary_search(mean - 1, mean + 1, |position| {
+            self.total(crabs, position)
+        }))
+    }
+}
+
+/// Leftmost minimum of a convex function on `lo..=hi`
+///
+/// Integer flavour of ternary search: the slope `f(p + 1) - f(p)` of a convex
+/// function never decreases, so the minimum is the first point with non-negative slope.
+fn ternary_search(mut lo: i64, mut hi: i64, f: impl Fn(i64) -> i64) -> Alignment {
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if f(mid + 1) >= f(mid) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+
+    Alignment {
+        position: lo,
+        fuel: f(lo),
+    }
+}
@@ -1,19 +1,26 @@
-use std::{error::Error, str::FromStr};
+use std::{error::Error, ops::RangeInclusive, str::FromStr};
 
 use advent_utils::{Part, Solver};
 
+pub use analysis::{best_meeting_points, curves_to_csv, FuelCurve, MeetingPoints};
+pub use cost::{sum_up_to_n, Alignment, CostFunction, Crabs, Linear, Triangular};
+
+mod analysis;
+mod cost;
+
 #[derive(Debug)]
 pub struct Solution {
     crab_positions: Vec<i64>,
     min_pos: i64,
     max_pos: i64,
+    crabs: Crabs,
 }
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(input_data: &str) -> Result<Self, Self::Err> {
-        let crab_positions = input_data
+        let crab_positions: Vec<i64> = input_data
             .trim_end()
             .split(',')
             .map(str::parse)
@@ -31,60 +38,237 @@ impl FromStr for Solution {
             }
         }
 
+        let crabs = Crabs::new(&crab_positions);
+
         Ok(Self {
             crab_positions,
             min_pos,
             max_pos,
+            crabs,
         })
     }
 }
 
-#[inline]
-fn sum_up_to_n(n: i64) -> i64 {
-    (n * (n + 1)) / 2
+impl Solution {
+    pub fn crab_positions(&self) -> &[i64] {
+        &self.crab_positions
+    }
+
+    /// Positions from the leftmost to the rightmost crab
+    pub fn position_range(&self) -> RangeInclusive<i64> {
+        self.min_pos..=self.max_pos
+    }
+
+    /// Cheapest position to align crabs at with given cost function, `None` if there are no crabs
+    pub fn align<C: CostFunction + ?Sized>(&self, cost: &C) -> Option<Alignment> {
+        cost.optimum(&self.crabs)
+    }
+
+    /// All positions tied for the minimal fuel usage, `None` if there are no crabs
+    pub fn optimal_positions<C: CostFunction + ?Sized>(
+        &self,
+        cost: &C,
+    ) -> Option<RangeInclusive<i64>> {
+        cost.optimal_positions(&self.crabs)
+    }
+
+    /// Fuel usage for every position from the leftmost to the rightmost crab
+    pub fn fuel_curve<C: CostFunction + ?Sized>(&self, cost: &C) -> FuelCurve {
+        FuelCurve::new(cost, &self.crabs, self.min_pos, self.max_pos)
+    }
+
+    /// CSV with fuel usage for every position and each of named cost functions
+    pub fn fuel_curves_csv(&self, costs: &[(&str, &dyn CostFunction)]) -> String {
+        let curves = costs
+            .iter()
+            .map(|&(name, cost)| (name, self.fuel_curve(cost)))
+            .collect::<Vec<_>>();
+
+        curves_to_csv(
+            &curves
+                .iter()
+                .map(|(name, curve)| (*name, curve))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    /// Cheapest way to split crabs into two groups, each aligning at its own position
+    pub fn best_meeting_points<C: CostFunction + ?Sized>(&self, cost: &C) -> Option<MeetingPoints> {
+        best_meeting_points(cost, &self.crabs)
+    }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
-        match part {
-            Part::One => {
-                let mut min_fuel = i64::MAX;
+        let alignment = match part {
+            Part::One => self.align(&Linear),
+            Part::Two => self.align(&Triangular),
+        };
 
-                for p in self.min_pos..=self.max_pos {
-                    let fuel = self
-                        .crab_positions
-                        .iter()
-                        .map(|&pos| (pos - p).abs())
-                        .sum::<i64>();
+        match alignment {
+            Some(Alignment { fuel, .. }) => format!("min fuel usage is {}", fuel),
+            None => "there are no crabs to align".to_string(),
+        }
+    }
 
-                    if fuel < min_fuel {
-                        min_fuel = fuel;
-                    }
-                }
+    fn day_number() -> u32 {
+        7
+    }
+}
 
-                format!("min fuel usage is {}", min_fuel)
-            }
-            Part::Two => {
-                let mut min_fuel = i64::MAX;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                for p in self.min_pos..=self.max_pos {
-                    let fuel = self
-                        .crab_positions
-                        .iter()
-                        .map(|&pos| sum_up_to_n((pos - p).abs()))
-                        .sum::<i64>();
+    /// Cost, growing with the cube of distance
+    struct Cubic;
 
-                    if fuel < min_fuel {
-                        min_fuel = fuel;
-                    }
-                }
+    impl CostFunction for Cubic {
+        fn cost(&self, distance: i64) -> i64 {
+            distance * distance * distance
+        }
+    }
+
+    fn brute_force<C: CostFunction>(solution: &Solution, cost: &C) -> Alignment {
+        (solution.min_pos..=solution.max_pos)
+            .map(|position| Alignment {
+                position,
+                fuel: solution
+                    .crab_positions
+                    .iter()
+                    .map(|&pos| cost.cost((pos - position).abs()))
+                    .sum(),
+            })
+            .min_by_key(|alignment| (alignment.fuel, alignment.position))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_short_example() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+
+        assert_eq!(
+            solution.align(&Linear),
+            Some(Alignment {
+                position: 2,
+                fuel: 37
+            })
+        );
+        assert_eq!(
+            solution.align(&Triangular),
+            Some(Alignment {
+                position: 5,
+                fuel: 168
+            })
+        );
+        assert_eq!(solution.solve(Part::One), "min fuel usage is 37");
+        assert_eq!(solution.solve(Part::Two), "min fuel usage is 168");
+    }
 
-                format!("min fuel usage is {}", min_fuel)
+    #[test]
+    fn test_matches_brute_force() {
+        let inputs = [
+            "16,1,2,0,4,2,7,1,2,14",
+            "5",
+            "3,3,3,9",
+            "0,1000,1000,2,500,7,999,13",
+            "1,2,3,4",
+        ];
+
+        for input in inputs {
+            let solution: Solution = input.parse().unwrap();
+
+            assert_eq!(
+                solution.align(&Linear),
+                Some(brute_force(&solution, &Linear))
+            );
+            assert_eq!(
+                solution.align(&Triangular),
+                Some(brute_force(&solution, &Triangular))
+            );
+            assert_eq!(solution.align(&Cubic), Some(brute_force(&solution, &Cubic)));
+
+            for position in solution.min_pos..=solution.max_pos {
+                assert_eq!(
+                    Triangular.total(&solution.crabs, position),
+                    solution
+                        .crab_positions
+                        .iter()
+                        .map(|&pos| sum_up_to_n((pos - position).abs()))
+                        .sum::<i64>()
+                );
             }
         }
     }
 
-    fn day_number() -> u32 {
-        7
+    #[test]
+    fn test_curves_and_ties() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+
+        let curve = solution.fuel_curve(&Linear);
+        assert_eq!(curve.points().count(), 17);
+        assert_eq!(curve.fuel_at(1), Some(41));
+        assert_eq!(curve.fuel_at(10), Some(71));
+        assert_eq!(curve.fuel_at(17), None);
+        assert_eq!(curve.min_positions(), [2]);
+        assert_eq!(solution.optimal_positions(&Linear), Some(2..=2));
+
+        let csv = solution.fuel_curves_csv(&[("linear", &Linear), ("triangular", &Triangular)]);
+        let mut rows = csv.lines();
+        assert_eq!(rows.next(), Some("position,linear,triangular"));
+        assert_eq!(rows.nth(2), Some("2,37,206"));
+        assert_eq!(rows.nth(2), Some("5,45,168"));
+        assert_eq!(rows.count(), 11);
+
+        // even number of crabs, every position between the middle ones is optimal
+        let solution: Solution = "1,2,7,10".parse().unwrap();
+        assert_eq!(solution.optimal_positions(&Linear), Some(2..=7));
+        assert_eq!(
+            solution.fuel_curve(&Linear).min_positions(),
+            [2, 3, 4, 5, 6, 7]
+        );
+
+        let solution: Solution = "0,3".parse().unwrap();
+        assert_eq!(solution.optimal_positions(&Triangular), Some(1..=2));
+        assert_eq!(solution.optimal_positions(&Cubic), Some(1..=2));
+    }
+
+    #[test]
+    fn test_best_meeting_points() {
+        let solution: Solution = "1,2,3,100,101,105".parse().unwrap();
+
+        let meeting_points = solution.best_meeting_points(&Linear).unwrap();
+        assert_eq!(meeting_points.left_size, 3);
+        assert_eq!(meeting_points.left.position, 2);
+        assert_eq!(meeting_points.right.position, 101);
+        assert_eq!(meeting_points.fuel(), 2 + 5);
+
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        for cost in [&Linear as &dyn CostFunction, &Triangular, &Cubic] {
+            let crabs = solution.crab_positions();
+            let best = solution.best_meeting_points(cost).unwrap();
+
+            // every crab goes to the nearest of every pair of points
+            let brute_force = solution
+                .position_range()
+                .flat_map(|a| solution.position_range().map(move |b| (a, b)))
+                .map(|(a, b)| {
+                    crabs
+                        .iter()
+                        .map(|&pos| cost.cost((pos - a).abs()).min(cost.cost((pos - b).abs())))
+                        .sum::<i64>()
+                })
+                .min()
+                .unwrap();
+
+            assert_eq!(best.fuel(), brute_force);
+        }
+
+        assert_eq!(
+            "5".parse::<Solution>()
+                .unwrap()
+                .best_meeting_points(&Linear),
+            None
+        );
     }
 }
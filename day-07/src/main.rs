@@ -1,9 +1,21 @@
-use std::error::Error;
+use std::{env::var, error::Error};
 
+use advent_input::read_input;
 use advent_utils::Solver;
 
-use day_07::Solution;
+use day_07::{Linear, Solution, Triangular};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    Solution::solve_env_config()
+    // `CURVE=1` prints fuel usage for every position as CSV instead of solving
+    if var("CURVE").is_err() {
+        return Solution::solve_env_config();
+    }
+
+    let solution: Solution = read_input()?;
+    print!(
+        "{}",
+        solution.fuel_curves_csv(&[("linear", &Linear), ("triangular", &Triangular)])
+    );
+
+    Ok(())
 }
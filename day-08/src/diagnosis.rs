@@ -0,0 +1,217 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    entry::{Entry, Segments, N_SEGMENTS},
+    glyphs::GlyphTable,
+    wiring::{digits_to_value, for_each_wiring, Wiring, ALL_SEGMENTS},
+};
+
+/// Number of segments, which may be flipped in a single pattern
+const MAX_FLIPPED_SEGMENTS: u32 = 1;
+
+/// Best guess about a display with incomplete or corrupted notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// wirings, turning the largest number of signal patterns into distinct digits
+    pub wirings: Vec<Wiring>,
+    /// signal patterns, which aren't a digit under some of the wirings
+    pub corrupted_patterns: Vec<Segments>,
+    /// possible digits for each output position, empty if it's unreadable
+    pub digits: Vec<BTreeSet<u8>>,
+    /// distinct ways to read the output under the wirings: possible digits for each
+    /// position, any combination of them is a possible output
+    pub readings: Vec<Vec<BTreeSet<u8>>>,
+    /// whether some possible outputs don't fit into u64
+    pub overflow: bool,
+    /// wires (as written in notes), which are probably flipped
+    pub suspect_wires: Segments,
+    /// display segments, which are probably flipped
+    pub suspect_segments: Segments,
+    base: u64,
+}
+
+impl Diagnosis {
+    /// Whether the display was decoded without any doubts
+    pub fn is_exact(&self) -> bool {
+        self.wirings.len() == 1
+            && self.corrupted_patterns.is_empty()
+            && self.digits.iter().all(|digits| digits.len() == 1)
+    }
+
+    /// Output positions with several possible digits
+    pub fn ambiguous_digits(&self) -> Vec<usize> {
+        self.digits
+            .iter()
+            .enumerate()
+            .filter(|(_, digits)| digits.len() > 1)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Possible output values, which fit into u64, built one by one from the readings.
+    /// A value may repeat, if several readings allow it.
+    pub fn values(&self) -> impl Iterator<Item = u64> + '_ {
+        self.readings
+            .iter()
+            .flat_map(|reading| Combinations::new(reading))
+            .filter_map(|digits| digits_to_value(&digits, self.base))
+    }
+}
+
+/// All combinations of digits, one for each position, in ascending order
+struct Combinations {
+    digits: Vec<Vec<u8>>,
+    /// index of the digit at each position in the next combination, `None` after the last one
+    next: Option<Vec<usize>>,
+}
+
+impl Combinations {
+    fn new(reading: &[BTreeSet<u8>]) -> Self {
+        let digits = reading
+            .iter()
+            .map(|digits| digits.iter().copied().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let next = (!digits.iter().any(Vec::is_empty)).then(|| vec![0; digits.len()]);
+
+        Self { digits, next }
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let indices = self.next.as_mut()?;
+        let combination = indices
+            .iter()
+            .zip(&self.digits)
+            .map(|(&i, digits)| digits[i])
+            .collect();
+
+        // advance the last position, carrying into the previous ones
+        let mut position = indices.len();
+        loop {
+            if position == 0 {
+                self.next = None;
+                break;
+            }
+            position -= 1;
+
+            indices[position] += 1;
+            if indices[position] < self.digits[position].len() {
+                break;
+            }
+            indices[position] = 0;
+        }
+
+        Some(combination)
+    }
+}
+
+/// Digits, closest to given segments, along with the flipped segments for each
+fn nearest_digits(segments: Segments, glyphs: &GlyphTable) -> Vec<(u8, Segments)> {
+    let distance = |glyph: Segments| (glyph ^ segments).count_ones();
+    let Some(min_distance) = glyphs.glyphs().iter().map(|&glyph| distance(glyph)).min() else {
+        return Vec::new();
+    };
+
+    if min_distance > MAX_FLIPPED_SEGMENTS {
+        return Vec::new();
+    }
+
+    glyphs
+        .glyphs()
+        .iter()
+        .enumerate()
+        .filter(|&(_, &glyph)| distance(glyph) == min_distance)
+        .map(|(digit, &glyph)| (digit as u8, glyph ^ segments))
+        .collect()
+}
+
+/// Number of distinct digits among patterns under the wiring
+fn score(wiring: &Wiring, patterns: &[Segments], glyphs: &GlyphTable) -> u32 {
+    patterns
+        .iter()
+        .filter_map(|&pattern| glyphs.digit(wiring.translate(pattern)))
+        .fold(0u128, |seen, digit| seen | 1 << digit)
+        .count_ones()
+}
+
+/// Decode the display, tolerating missing, corrupted or flipped patterns
+///
+/// Every wiring is tried, the ones with the most signal patterns matching distinct
+/// digits are kept, and outputs are read as the nearest digits under each of them.
+pub fn diagnose(entry: &Entry, glyphs: &GlyphTable) -> Diagnosis {
+    let mut wirings = Vec::new();
+    let mut best_score = 0;
+
+    for_each_wiring(&[ALL_SEGMENTS; N_SEGMENTS], &mut |wiring| {
+        let score = score(&wiring, &entry.patterns, glyphs);
+
+        if score > best_score {
+            best_score = score;
+            wirings.clear();
+        }
+        if score == best_score {
+            wirings.push(wiring);
+        }
+
+        true
+    });
+
+    let mut corrupted_patterns = BTreeSet::new();
+    let mut digits = vec![BTreeSet::new(); entry.output.len()];
+    let mut readings = BTreeSet::new();
+    let (mut suspect_wires, mut suspect_segments) = (0, 0);
+    let base = glyphs.base() as u64;
+
+    let mut note_flips = |wiring: &Wiring, flipped: Segments| {
+        suspect_segments |= flipped;
+        suspect_wires |= wiring.wires(flipped);
+    };
+
+    for wiring in &wirings {
+        for &pattern in &entry.patterns {
+            let segments = wiring.translate(pattern);
+
+            if !glyphs.contains(segments) {
+                corrupted_patterns.insert(pattern);
+                for (_, flipped) in nearest_digits(segments, glyphs) {
+                    note_flips(wiring, flipped);
+                }
+            }
+        }
+
+        let mut reading = vec![BTreeSet::new(); entry.output.len()];
+        for (position, &pattern) in entry.output.iter().enumerate() {
+            for (digit, flipped) in nearest_digits(wiring.translate(pattern), glyphs) {
+                note_flips(wiring, flipped);
+                digits[position].insert(digit);
+                reading[position].insert(digit);
+            }
+        }
+
+        readings.insert(reading);
+    }
+
+    // the largest combination of a reading overflows, if any of them does
+    let overflow = readings.iter().any(|reading| {
+        let largest = reading
+            .iter()
+            .map(|digits| digits.last().copied())
+            .collect::<Option<Vec<_>>>();
+
+        largest.is_some_and(|largest| digits_to_value(&largest, base).is_none())
+    });
+
+    Diagnosis {
+        wirings,
+        corrupted_patterns: corrupted_patterns.into_iter().collect(),
+        digits,
+        readings: readings.into_iter().collect(),
+        overflow,
+        suspect_wires,
+        suspect_segments,
+        base,
+    }
+}
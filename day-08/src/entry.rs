@@ -0,0 +1,67 @@
+use std::str::FromStr;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Number of segments (and wires) of the display
+pub const N_SEGMENTS: usize = 7;
+
+/// Set of segments (or wires) `a..=g` as a bitmask, `a` is the lowest bit
+pub type Segments = u8;
+
+/// Notes, taken for a single display
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// unique signal patterns, seen on the display
+    pub patterns: Vec<Segments>,
+    /// wires, lit for each output digit
+    pub output: Vec<Segments>,
+}
+
+/// Display entry parsing error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ParseEntryError {
+    /// no `|` between signal patterns and output
+    MissingSeparator,
+    /// invalid wire {found:?} in pattern {pattern:?}
+    InvalidWire { pattern: String, found: char },
+}
+
+/// Parse pattern like `cfbegad` into segments bitmask
+pub fn parse_segments(pattern: &str) -> Result<Segments, ParseEntryError> {
+    pattern.chars().try_fold(0, |segments, wire| match wire {
+        'a'..='g' => Ok(segments | 1 << (wire as u8 - b'a')),
+        found => Err(ParseEntryError::InvalidWire {
+            pattern: pattern.to_owned(),
+            found,
+        }),
+    })
+}
+
+/// Format segments bitmask as letters in alphabetical order
+pub fn segments_to_string(segments: Segments) -> String {
+    (0..N_SEGMENTS as u8)
+        .filter(|&i| segments & (1 << i) != 0)
+        .map(|i| char::from(b'a' + i))
+        .collect()
+}
+
+impl FromStr for Entry {
+    type Err = ParseEntryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (patterns, output) = s.split_once('|').ok_or(ParseEntryError::MissingSeparator)?;
+
+        let parse_all = |patterns: &str| {
+            patterns
+                .split_ascii_whitespace()
+                .map(parse_segments)
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            patterns: parse_all(patterns)?,
+            output: parse_all(output)?,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+use std::{fmt, str::FromStr};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::entry::{parse_segments, segments_to_string, ParseEntryError, Segments};
+
+/// Segments of decimal digits 0–9 on a correctly wired display
+const DECIMAL_GLYPHS: [Segments; 10] = [
+    0b1110111, // 0: abcefg
+    0b0100100, // 1: cf
+    0b1011101, // 2: acdeg
+    0b1101101, // 3: acdfg
+    0b0101110, // 4: bcdf
+    0b1101011, // 5: abdfg
+    0b1111011, // 6: abdefg
+    0b0100101, // 7: acf
+    0b1111111, // 8: abcdefg
+    0b1101111, // 9: abcdfg
+];
+
+/// Segments of hex digits A–F, following decimal ones
+const HEX_LETTER_GLYPHS: [Segments; 6] = [
+    0b0111111, // A: abcdef
+    0b1111010, // b: bdefg
+    0b1010011, // C: abeg
+    0b1111100, // d: cdefg
+    0b1011011, // E: abdeg
+    0b0011011, // F: abde
+];
+
+/// Font of the display: segments for each digit, in order of their values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlyphTable {
+    glyphs: Vec<Segments>,
+}
+
+/// Glyph table error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum GlyphTableError {
+    /// glyph table should contain at least two digits
+    TooFewGlyphs,
+    /// digit {0} has no lit segments
+    EmptyGlyph(usize),
+    /// digits {first} and {second} have the same glyph
+    DuplicateGlyph { first: usize, second: usize },
+    /// invalid glyph: {0}
+    InvalidGlyph(#[from] ParseEntryError),
+}
+
+impl Default for GlyphTable {
+    fn default() -> Self {
+        Self::decimal()
+    }
+}
+
+impl GlyphTable {
+    pub fn new(glyphs: Vec<Segments>) -> Result<Self, GlyphTableError> {
+        if glyphs.len() < 2 {
+            return Err(GlyphTableError::TooFewGlyphs);
+        }
+
+        for (digit, glyph) in glyphs.iter().enumerate() {
+            if *glyph == 0 {
+                return Err(GlyphTableError::EmptyGlyph(digit));
+            }
+            if let Some(first) = glyphs[..digit].iter().position(|other| other == glyph) {
+                return Err(GlyphTableError::DuplicateGlyph {
+                    first,
+                    second: digit,
+                });
+            }
+        }
+
+        Ok(Self { glyphs })
+    }
+
+    /// Standard digits 0–9
+    pub fn decimal() -> Self {
+        Self {
+            glyphs: DECIMAL_GLYPHS.to_vec(),
+        }
+    }
+
+    /// Digits 0–9 followed by letters A–F
+    pub fn hexadecimal() -> Self {
+        Self {
+            glyphs: DECIMAL_GLYPHS
+                .iter()
+                .chain(&HEX_LETTER_GLYPHS)
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Number of digits, which is also the base of displayed numbers
+    pub fn base(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn glyphs(&self) -> &[Segments] {
+        &self.glyphs
+    }
+
+    /// Digit, shown by given segments
+    pub fn digit(&self, segments: Segments) -> Option<u8> {
+        self.glyphs
+            .iter()
+            .position(|&glyph| glyph == segments)
+            .map(|digit| digit as u8)
+    }
+
+    pub fn contains(&self, segments: Segments) -> bool {
+        self.glyphs.contains(&segments)
+    }
+
+    /// Whether the digit can be recognized just by the number of lit segments
+    pub fn is_easy(&self, segments: Segments) -> bool {
+        self.glyphs
+            .iter()
+            .filter(|glyph| glyph.count_ones() == segments.count_ones())
+            .count()
+            == 1
+    }
+}
+
+impl FromStr for GlyphTable {
+    type Err = GlyphTableError;
+
+    /// Whitespace separated segments of each digit, e.g. `abcefg cf acdeg ...`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let glyphs = s
+            .split_ascii_whitespace()
+            .map(parse_segments)
+            .collect::<Result<_, _>>()?;
+
+        Self::new(glyphs)
+    }
+}
+
+impl fmt::Display for GlyphTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let glyphs = self
+            .glyphs
+            .iter()
+            .map(|&glyph| segments_to_string(glyph))
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", glyphs.join(" "))
+    }
+}
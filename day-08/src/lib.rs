@@ -2,81 +2,56 @@ use std::{error::Error, str::FromStr};
 
 use advent_utils::{parse_raw_data, Part, Solver};
 
+pub use diagnosis::{diagnose, Diagnosis};
+pub use entry::{parse_segments, segments_to_string, Entry, ParseEntryError, Segments, N_SEGMENTS};
+pub use glyphs::{GlyphTable, GlyphTableError};
+pub use wiring::{decode, deduce_wiring, Decoded, SolveError, Wiring};
+
+mod diagnosis;
+mod entry;
+mod glyphs;
+mod wiring;
+
 #[derive(Debug)]
 pub struct Solution {
-    signals: Vec<String>,
+    entries: Vec<Entry>,
+    glyphs: GlyphTable,
 }
 
-const OUTPUT_DIGITS_NUMBER: usize = 4;
-
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(input_data: &str) -> Result<Self, Self::Err> {
-        let signals = parse_raw_data(input_data)?;
+        let entries = parse_raw_data(input_data)?;
 
-        Ok(Self { signals })
+        Ok(Self {
+            entries,
+            glyphs: GlyphTable::default(),
+        })
     }
 }
 
-fn digit_to_segments_map(digit: &str) -> u8 {
-    digit
-        .bytes()
-        .fold(0, |map, segment| map | (1 << (segment - b'a')))
-}
-
-fn deduce_signal_scheme(signal: &str) -> Option<u32> {
-    let (digits, output) = signal.split_once('|').unwrap();
-    let digits = digits
-        .split_ascii_whitespace()
-        .map(digit_to_segments_map)
-        .collect::<Vec<_>>();
-
-    let one = *digits.iter().find(|&digit| digit.count_ones() == 2)?;
-    let four = *digits.iter().find(|&digit| digit.count_ones() == 4)?;
-    let seven = *digits.iter().find(|&digit| digit.count_ones() == 3)?;
-    let eight = (1u8 << 7) - 1;
-
-    let a_segment = 1u8 << (seven ^ one).trailing_zeros();
-
-    let nine_without_g = four | a_segment;
-    let nine = *digits
-        .iter()
-        .find(|&&digit| digit > nine_without_g && (digit ^ nine_without_g).count_ones() == 1)?;
-    let g_segment = nine ^ nine_without_g;
-    let e_segment = eight ^ nine;
-
-    let two_without_dc = a_segment | e_segment | g_segment;
-
-    let two = *digits
-        .iter()
-        .find(|&&digit| digit > two_without_dc && (digit ^ two_without_dc).count_ones() == 2)?;
-
-    let c_segment = two & one;
-    let f_segment = one ^ c_segment;
-    let d_segment = two ^ (two_without_dc | c_segment);
-
-    let zero = eight ^ d_segment;
-
-    let three = two ^ e_segment | f_segment;
-    let five = nine ^ c_segment;
-    let six = five | e_segment;
-
-    let digits_array = [zero, one, two, three, four, five, six, seven, eight, nine];
-
-    let mut result = 0;
+impl Solution {
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
 
-    for (i, digit_map) in output
-        .split_ascii_whitespace()
-        .map(digit_to_segments_map)
-        .enumerate()
-    {
-        let digit = digits_array.iter().position(|&digit| digit == digit_map)? as u32;
+    /// Use given font instead of standard decimal digits
+    pub fn with_glyphs(self, glyphs: GlyphTable) -> Self {
+        Self { glyphs, ..self }
+    }
 
-        result += digit * 10u32.pow((OUTPUT_DIGITS_NUMBER - i - 1) as u32);
+    /// Decode each entry, or tell why it can't be decoded
+    pub fn decode_all(&self) -> impl Iterator<Item = Result<Decoded, SolveError>> + '_ {
+        self.entries.iter().map(|entry| decode(entry, &self.glyphs))
     }
 
-    Some(result)
+    /// Best guess for each entry, tolerating missing and corrupted patterns
+    pub fn diagnose_all(&self) -> impl Iterator<Item = Diagnosis> + '_ {
+        self.entries
+            .iter()
+            .map(|entry| diagnose(entry, &self.glyphs))
+    }
 }
 
 impl Solver for Solution {
@@ -84,26 +59,29 @@ impl Solver for Solution {
         match part {
             Part::One => {
                 let easy = self
-                    .signals
+                    .entries
                     .iter()
-                    .map(|signal| {
-                        let (_digits, output) = signal.split_once('|').unwrap();
-
-                        output
-                            .split_whitespace()
-                            .filter(|&digit| matches!(digit.len(), 2 | 3 | 4 | 7))
-                            .count()
-                    })
-                    .sum::<usize>();
+                    .flat_map(|entry| &entry.output)
+                    .filter(|&&pattern| self.glyphs.is_easy(pattern))
+                    .count();
 
                 format!("there are {} easy digits", easy)
             }
             Part::Two => {
-                let sum = self
-                    .signals
-                    .iter()
-                    .map(|signal| deduce_signal_scheme(signal).expect("failed to deduce"))
-                    .sum::<u32>();
+                let mut sum = 0u64;
+
+                for (i, decoded) in self.decode_all().enumerate() {
+                    let value = match decoded.map(|decoded| decoded.value()) {
+                        Ok(Some(value)) => value,
+                        Ok(None) => return format!("output of entry #{} is too big", i),
+                        Err(e) => return format!("failed to decode entry #{}: {}", i, e),
+                    };
+
+                    sum = match sum.checked_add(value) {
+                        Some(sum) => sum,
+                        None => return "sum of output signals is too big".to_owned(),
+                    };
+                }
 
                 format!("sum of output signals is {}", sum)
             }
@@ -117,34 +95,207 @@ impl Solver for Solution {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+
     use super::*;
 
+    fn decode_value(entry: &str) -> Result<u64, SolveError> {
+        decode(&entry.parse().unwrap(), &GlyphTable::decimal())
+            .map(|decoded| decoded.value().unwrap())
+    }
+
     #[test]
     fn test_first_signal() {
-        assert_eq!(
-            deduce_signal_scheme("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf"),
-            Some(5353),
-        )
+        let entry =
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf";
+        let decoded = decode(&entry.parse().unwrap(), &GlyphTable::decimal()).unwrap();
+
+        assert_eq!(decoded.digits, [5, 3, 5, 3]);
+        assert_eq!(decoded.value(), Some(5353));
+        assert_eq!(decoded.wiring.to_string(), "cfgabde");
     }
 
     #[test]
     fn test_first_failure() {
         assert_eq!(
-            deduce_signal_scheme("aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea"),
-            Some(4873),
+            decode_value("aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea"),
+            Ok(4873),
         )
     }
 
     #[test]
     fn test_short_example() {
-        let input = include_str!("../short.txt");
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+
+        assert_eq!(solution.solve(Part::One), "there are 26 easy digits");
+        assert_eq!(solution.solve(Part::Two), "sum of output signals is 61229");
+    }
+
+    #[test]
+    fn test_unsolvable_entries() {
+        // any number of output digits
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | ab dab eafb acedgfb cdfbe cefabd"),
+            Ok(174_859),
+        );
 
         assert_eq!(
-            input
-                .split('\n')
-                .map(|signal| deduce_signal_scheme(signal).expect("failed to deduce"))
-                .sum::<u32>(),
-            61229
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb | cdfeb"),
+            Err(SolveError::PatternCount {
+                expected: 10,
+                found: 9
+            }),
+        );
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb ab ab | cdfeb"),
+            Err(SolveError::DuplicatePattern("ab".to_owned())),
+        );
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb a | cdfeb"),
+            Err(SolveError::ImpossiblePattern("a".to_owned())),
+        );
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ac | cdfeb"),
+            Err(SolveError::NoWiring),
+        );
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb abc"),
+            Err(SolveError::UnknownOutput {
+                position: 1,
+                pattern: "abc".to_owned()
+            }),
+        );
+
+        assert_eq!(
+            "acedgfb cdfbe gcdfa".parse::<Entry>(),
+            Err(ParseEntryError::MissingSeparator)
+        );
+        assert!("acedgfb cdfbe | gcdfx".parse::<Entry>().is_err());
+    }
+
+    #[test]
+    fn test_diagnosis() {
+        let diagnose_str = |entry: &str| diagnose(&entry.parse().unwrap(), &GlyphTable::decimal());
+
+        let exact = diagnose_str(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab | cdfeb fcadb cdfeb cdbaf",
+        );
+        assert!(exact.is_exact());
+        assert_eq!(
+            exact.values().collect::<BTreeSet<_>>(),
+            BTreeSet::from([5353])
+        );
+        assert_eq!((exact.suspect_wires, exact.suspect_segments), (0, 0));
+
+        // `1` is missing, `4` has lost its `b` wire (`f` segment)
+        let corrupted = diagnose_str(
+            "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eaf cagedb | cdfeb fcadb cdfe acedgf",
+        );
+        assert_eq!(
+            decode_value("acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eaf cagedb | cdfeb"),
+            Err(SolveError::PatternCount {
+                expected: 10,
+                found: 9
+            })
+        );
+        assert_eq!(corrupted.wirings.len(), 1);
+        assert_eq!(corrupted.wirings[0].to_string(), "cfgabde");
+        assert_eq!(
+            corrupted.corrupted_patterns,
+            [parse_segments("aef").unwrap()]
+        );
+        assert_eq!(
+            corrupted.digits,
+            [
+                BTreeSet::from([5]),
+                BTreeSet::from([3]),
+                BTreeSet::from([5]),
+                BTreeSet::from([2, 8]),
+            ]
+        );
+        assert_eq!(corrupted.ambiguous_digits(), [3]);
+        assert_eq!(
+            corrupted.values().collect::<BTreeSet<_>>(),
+            BTreeSet::from([5352, 5358])
+        );
+        // `acedgf` is either `8` without `b` wire or `2` with extra `e` wire
+        assert_eq!(segments_to_string(corrupted.suspect_wires), "be");
+        assert_eq!(segments_to_string(corrupted.suspect_segments), "bf");
+        assert!(!corrupted.is_exact());
+
+        // values are built one by one, instead of all combinations at once
+        let patterns = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eaf cagedb";
+        let ambiguous = diagnose_str(&format!("{} | {}", patterns, ["acedgf"; 6].join(" ")));
+        assert_eq!(ambiguous.readings.len(), 1);
+        assert_eq!(ambiguous.values().count(), 64);
+        assert_eq!(
+            ambiguous.values().take(2).collect::<Vec<_>>(),
+            [222222, 222228]
+        );
+        assert!(!ambiguous.overflow);
+
+        let long = diagnose_str(&format!("{} | {}", patterns, ["cdfeb"; 20].join(" ")));
+        assert!(long.overflow);
+        assert_eq!(long.values().next(), None);
+    }
+
+    #[test]
+    fn test_glyph_tables() {
+        let hex = GlyphTable::hexadecimal();
+        assert_eq!(hex.base(), 16);
+
+        // scramble wires with `cfgabde` wiring: wire `a` lights segment `c` and so on
+        let wire_for_segment = |segment: usize| b"deafgbc"[segment] - b'a';
+        let scramble = |glyph: Segments| {
+            let wires = (0..N_SEGMENTS)
+                .filter(|&segment| glyph & (1 << segment) != 0)
+                .fold(0, |wires, segment| wires | 1 << wire_for_segment(segment));
+
+            segments_to_string(wires)
+        };
+        let patterns = hex
+            .glyphs()
+            .iter()
+            .rev()
+            .map(|&glyph| scramble(glyph))
+            .collect::<Vec<_>>();
+        let output = [0xC, 0xA, 0xF, 0xE, 0x4, 2]
+            .iter()
+            .map(|&digit| scramble(hex.glyphs()[digit]))
+            .collect::<Vec<_>>();
+        let input = format!("{} | {}", patterns.join(" "), output.join(" "));
+
+        let solution: Solution = input.parse::<Solution>().unwrap().with_glyphs(hex);
+        let decoded = solution.decode_all().next().unwrap().unwrap();
+        assert_eq!(decoded.wiring.to_string(), "cfgabde");
+        assert_eq!(decoded.value(), Some(0xCAFE42));
+
+        // decimal font doesn't know about letters
+        let decimal: Solution = input.parse().unwrap();
+        assert_eq!(
+            decimal.decode_all().next().unwrap(),
+            Err(SolveError::PatternCount {
+                expected: 10,
+                found: 16
+            })
+        );
+
+        let custom: GlyphTable = "abcefg cf acdeg acdfg".parse().unwrap();
+        assert_eq!(custom.to_string(), "abcefg cf acdeg acdfg");
+        assert!(custom.is_easy(parse_segments("ab").unwrap()));
+        assert!(!custom.is_easy(parse_segments("abcde").unwrap()));
+
+        assert_eq!(
+            "abcefg".parse::<GlyphTable>(),
+            Err(GlyphTableError::TooFewGlyphs)
+        );
+        assert_eq!(
+            "abcefg cf gfecba".parse::<GlyphTable>(),
+            Err(GlyphTableError::DuplicateGlyph {
+                first: 0,
+                second: 2
+            })
         );
+        assert!("abcefg cx".parse::<GlyphTable>().is_err());
     }
 }
@@ -0,0 +1,260 @@
+use std::fmt;
+
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::{
+    entry::{segments_to_string, Entry, Segments, N_SEGMENTS},
+    glyphs::GlyphTable,
+};
+
+pub(crate) const ALL_SEGMENTS: Segments = (1 << N_SEGMENTS) - 1;
+
+/// Mapping from signal wires to display segments
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Wiring {
+    /// segment index for each wire index
+    segments: [u8; N_SEGMENTS],
+}
+
+/// Reason, why display wiring can't be deduced
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum SolveError {
+    /// expected {expected} unique signal patterns, found {found}
+    PatternCount { expected: usize, found: usize },
+    /// signal pattern {0:?} is repeated
+    DuplicatePattern(String),
+    /// signal pattern {0:?} doesn't look like any digit
+    ImpossiblePattern(String),
+    /// no wiring is consistent with all signal patterns
+    NoWiring,
+    /// {0} different wirings are consistent with signal patterns
+    AmbiguousWiring(usize),
+    /// output pattern {pattern:?} at position {position} doesn't match any digit
+    UnknownOutput { position: usize, pattern: String },
+}
+
+/// Display, decoded from notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub wiring: Wiring,
+    /// output digits, the most significant first
+    pub digits: Vec<u8>,
+    /// base of the glyph table, used for decoding
+    base: u64,
+}
+
+impl Wiring {
+    /// Segment, lit by the wire (both as indices)
+    pub fn segment(&self, wire: usize) -> usize {
+        usize::from(self.segments[wire])
+    }
+
+    /// Segments, lit by given set of wires
+    pub fn translate(&self, wires: Segments) -> Segments {
+        (0..N_SEGMENTS)
+            .filter(|&wire| wires & (1 << wire) != 0)
+            .fold(0, |segments, wire| segments | 1 << self.segment(wire))
+    }
+
+    /// Wires, lighting given set of segments
+    pub fn wires(&self, segments: Segments) -> Segments {
+        (0..N_SEGMENTS)
+            .filter(|&wire| segments & (1 << self.segment(wire)) != 0)
+            .fold(0, |wires, wire| wires | 1 << wire)
+    }
+}
+
+impl fmt::Display for Wiring {
+    /// Segments for wires `a..=g`, e.g. `deafgbc` means `a → d`, `b → e` and so on
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for wire in 0..N_SEGMENTS {
+            write!(f, "{}", segments_to_string(1 << self.segment(wire)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Decoded {
+    /// Output as a number, `None` on overflow
+    pub fn value(&self) -> Option<u64> {
+        digits_to_value(&self.digits, self.base)
+    }
+}
+
+/// Number, shown by digits in given base, `None` on overflow
+pub(crate) fn digits_to_value(digits: &[u8], base: u64) -> Option<u64> {
+    digits.iter().try_fold(0u64, |value, &digit| {
+        value.checked_mul(base)?.checked_add(u64::from(digit))
+    })
+}
+
+/// Possible segments for each wire, consistent with every pattern on its own
+///
+/// A wire of a pattern may light only segments of some digit of the same size,
+/// while a wire outside of it can't light a segment, common to all such digits.
+fn initial_candidates(
+    patterns: &[Segments],
+    glyphs: &GlyphTable,
+) -> Result<[Segments; N_SEGMENTS], SolveError> {
+    let mut candidates = [ALL_SEGMENTS; N_SEGMENTS];
+
+    for &pattern in patterns {
+        let (union, intersection) = glyphs
+            .glyphs()
+            .iter()
+            .filter(|glyph| glyph.count_ones() == pattern.count_ones())
+            .fold((0, ALL_SEGMENTS), |(union, intersection), &glyph| {
+                (union | glyph, intersection & glyph)
+            });
+
+        if union == 0 {
+            return Err(SolveError::ImpossiblePattern(segments_to_string(pattern)));
+        }
+
+        for (wire, wire_candidates) in candidates.iter_mut().enumerate() {
+            if pattern & (1 << wire) != 0 {
+                *wire_candidates &= union;
+            } else {
+                *wire_candidates &= !intersection;
+            }
+        }
+    }
+
+    Ok(candidates)
+}
+
+/// Narrow candidates down, using that wiring is a permutation:
+/// a segment, decided for one wire, is not available to others,
+/// and a segment, available to a single wire, is decided for it
+fn propagate(candidates: &mut [Segments; N_SEGMENTS]) -> bool {
+    loop {
+        let before = *candidates;
+
+        for wire in 0..N_SEGMENTS {
+            if candidates[wire].count_ones() == 1 {
+                let decided = candidates[wire];
+                for (other, other_candidates) in candidates.iter_mut().enumerate() {
+                    if other != wire {
+                        *other_candidates &= !decided;
+                    }
+                }
+            }
+        }
+
+        for segment in 0..N_SEGMENTS {
+            let mut wires = (0..N_SEGMENTS).filter(|&wire| candidates[wire] & (1 << segment) != 0);
+
+            if let (Some(wire), None) = (wires.next(), wires.next()) {
+                candidates[wire] = 1 << segment;
+            }
+        }
+
+        if candidates.contains(&0) {
+            return false;
+        }
+        if *candidates == before {
+            return true;
+        }
+    }
+}
+
+/// Call `visit` for every permutation, allowed by candidates, until it returns `false`
+pub(crate) fn for_each_wiring(
+    candidates: &[Segments; N_SEGMENTS],
+    visit: &mut impl FnMut(Wiring) -> bool,
+) {
+    fn search(
+        candidates: &[Segments; N_SEGMENTS],
+        wire: usize,
+        used: Segments,
+        wiring: &mut Wiring,
+        visit: &mut impl FnMut(Wiring) -> bool,
+    ) -> bool {
+        if wire == N_SEGMENTS {
+            return visit(*wiring);
+        }
+
+        let available = candidates[wire] & !used;
+        for segment in 0..N_SEGMENTS as u8 {
+            if available & (1 << segment) != 0 {
+                wiring.segments[wire] = segment;
+                if !search(candidates, wire + 1, used | 1 << segment, wiring, visit) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    let mut candidates = *candidates;
+    if propagate(&mut candidates) {
+        let mut wiring = Wiring {
+            segments: [0; N_SEGMENTS],
+        };
+        search(&candidates, 0, 0, &mut wiring, visit);
+    }
+}
+
+/// The only wiring, which turns every pattern into some digit of the glyph table
+pub fn deduce_wiring(patterns: &[Segments], glyphs: &GlyphTable) -> Result<Wiring, SolveError> {
+    if patterns.len() != glyphs.base() {
+        return Err(SolveError::PatternCount {
+            expected: glyphs.base(),
+            found: patterns.len(),
+        });
+    }
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        if patterns[..i].contains(pattern) {
+            return Err(SolveError::DuplicatePattern(segments_to_string(*pattern)));
+        }
+    }
+
+    let candidates = initial_candidates(patterns, glyphs)?;
+
+    let mut found = Vec::new();
+    for_each_wiring(&candidates, &mut |wiring| {
+        if patterns
+            .iter()
+            .all(|&pattern| glyphs.contains(wiring.translate(pattern)))
+        {
+            found.push(wiring);
+        }
+
+        true
+    });
+
+    match found.as_slice() {
+        [] => Err(SolveError::NoWiring),
+        &[wiring] => Ok(wiring),
+        wirings => Err(SolveError::AmbiguousWiring(wirings.len())),
+    }
+}
+
+/// Deduce wiring of the display, showing digits of the glyph table, and decode its output
+pub fn decode(entry: &Entry, glyphs: &GlyphTable) -> Result<Decoded, SolveError> {
+    let wiring = deduce_wiring(&entry.patterns, glyphs)?;
+
+    let digits = entry
+        .output
+        .iter()
+        .enumerate()
+        .map(|(position, &pattern)| {
+            glyphs
+                .digit(wiring.translate(pattern))
+                .ok_or_else(|| SolveError::UnknownOutput {
+                    position,
+                    pattern: segments_to_string(pattern),
+                })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(Decoded {
+        wiring,
+        digits,
+        base: glyphs.base() as u64,
+    })
+}
@@ -0,0 +1,286 @@
+use crate::config::{Config, Mode};
+
+const NO_BASIN: u32 = u32::MAX;
+
+/// Exit of a plateau with no lower neighbours
+const SINK: u32 = u32::MAX;
+/// Exit of a plateau, draining off the map
+const OUTSIDE: u32 = u32::MAX - 1;
+
+/// Disjoint set of cells with union by rank and path halving
+#[derive(Debug)]
+struct UnionFind {
+    parent: Vec<u32>,
+    rank: Vec<u8>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        Self {
+            parent: (0..n as u32).collect(),
+            rank: vec![0; n],
+        }
+    }
+
+    fn find(&mut self, mut x: u32) -> u32 {
+        while self.parent[x as usize] != x {
+            let grandparent = self.parent[self.parent[x as usize] as usize];
+            self.parent[x as usize] = grandparent;
+            x = grandparent;
+        }
+
+        x
+    }
+
+    fn union(&mut self, a: u32, b: u32) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+
+        if self.rank[a as usize] < self.rank[b as usize] {
+            (a, b) = (b, a);
+        }
+        self.parent[b as usize] = a;
+        if self.rank[a as usize] == self.rank[b as usize] {
+            self.rank[a as usize] += 1;
+        }
+    }
+}
+
+/// Area, surrounded by walls, or all cells draining to the same low point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Basin {
+    /// the lowest cell as `(x, y)`, the first one in reading order if there are several
+    pub low_point: (usize, usize),
+    pub low_height: u32,
+    /// number of cells
+    pub size: usize,
+    /// difference between the highest and the lowest cells
+    pub depth: u32,
+}
+
+/// Basin of every cell of the heightmap
+#[derive(Debug, Clone)]
+pub struct BasinMap {
+    width: usize,
+    height: usize,
+    labels: Vec<u32>,
+    basins: Vec<Basin>,
+}
+
+impl BasinMap {
+    /// Label basins, numbered in reading order of their first cells
+    pub fn new(
+        width: usize,
+        height: usize,
+        cell_height: impl Fn(usize, usize) -> u32,
+        config: &Config,
+    ) -> Self {
+        assert!(
+            width * height < OUTSIDE as usize,
+            "heightmap is too big to be labeled"
+        );
+
+        let size = (width, height);
+        let mut union_find = UnionFind::new(width * height);
+
+        // in wall mode connected areas are basins, in watershed mode they are plateaus
+        for y in 0..height {
+            for x in 0..width {
+                let current = cell_height(x, y);
+                if config.is_wall(current) {
+                    continue;
+                }
+
+                for (nx, ny) in config.neighbours((x, y), size).flatten() {
+                    let neighbour = cell_height(nx, ny);
+
+                    let connected = match config.mode {
+                        Mode::Walls => !config.is_wall(neighbour),
+                        Mode::Watershed => neighbour == current,
+                    };
+                    if connected {
+                        union_find.union((y * width + x) as u32, (ny * width + nx) as u32);
+                    }
+                }
+            }
+        }
+
+        // root of the basin for each cell at first, replaced with basin ids later
+        let mut labels = match config.mode {
+            Mode::Walls => (0..width * height)
+                .map(|cell| union_find.find(cell as u32))
+                .collect::<Vec<_>>(),
+            Mode::Watershed => sinks(width, height, &cell_height, config, &mut union_find),
+        };
+
+        // union-find isn't needed anymore, so its memory is reused for ids of roots
+        let mut root_labels = union_find.parent;
+        root_labels.fill(NO_BASIN);
+
+        let mut basins: Vec<Basin> = Vec::new();
+        let mut highest = Vec::new();
+
+        for y in 0..height {
+            for x in 0..width {
+                let cell = y * width + x;
+                let cell_height = cell_height(x, y);
+                if config.is_wall(cell_height) || labels[cell] == NO_BASIN {
+                    labels[cell] = NO_BASIN;
+                    continue;
+                }
+
+                let root = labels[cell] as usize;
+                if root_labels[root] == NO_BASIN {
+                    root_labels[root] = basins.len() as u32;
+                    basins.push(Basin {
+                        low_point: (x, y),
+                        low_height: cell_height,
+                        size: 0,
+                        depth: 0,
+                    });
+                    highest.push(cell_height);
+                }
+
+                let label = root_labels[root];
+                labels[cell] = label;
+
+                let basin = &mut basins[label as usize];
+                basin.size += 1;
+                if cell_height < basin.low_height {
+                    basin.low_point = (x, y);
+                    basin.low_height = cell_height;
+                }
+                highest[label as usize] = highest[label as usize].max(cell_height);
+            }
+        }
+
+        for (basin, highest) in basins.iter_mut().zip(highest) {
+            basin.depth = highest - basin.low_height;
+        }
+
+        Self {
+            width,
+            height,
+            labels,
+            basins,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Basin id of the cell, `None` for walls
+    pub fn label(&self, x: usize, y: usize) -> Option<usize> {
+        match self.labels[y * self.width + x] {
+            NO_BASIN => None,
+            label => Some(label as usize),
+        }
+    }
+
+    /// All basins, indexed by id
+    pub fn basins(&self) -> &[Basin] {
+        &self.basins
+    }
+
+    /// Sizes of `n` largest basins, the largest first
+    pub fn largest_sizes(&self, n: usize) -> Vec<usize> {
+        let mut sizes = self
+            .basins
+            .iter()
+            .map(|basin| basin.size)
+            .collect::<Vec<_>>();
+        sizes.sort_unstable_by(|a, b| b.cmp(a));
+        sizes.truncate(n);
+
+        sizes
+    }
+}
+
+/// Root of the plateau, where water ends up from each cell,
+/// `NO_BASIN` for walls and cells, draining off the map
+fn sinks(
+    width: usize,
+    height: usize,
+    cell_height: &impl Fn(usize, usize) -> u32,
+    config: &Config,
+    union_find: &mut UnionFind,
+) -> Vec<u32> {
+    let size = (width, height);
+    // open space beyond the edge is lower than any cell
+    let exit_height = |exit: u32| match exit {
+        SINK => Some(u32::MAX),
+        OUTSIDE => None,
+        cell => Some(cell_height(cell as usize % width, cell as usize / width)),
+    };
+
+    // lowest exit from each plateau, the first one in reading order if there are several
+    let mut exits = vec![SINK; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let current = cell_height(x, y);
+            if config.is_wall(current) {
+                continue;
+            }
+
+            let root = union_find.find((y * width + x) as u32) as usize;
+            for neighbour in config.neighbours((x, y), size) {
+                let exit = match neighbour {
+                    Some((nx, ny)) => {
+                        let neighbour_height = cell_height(nx, ny);
+                        if neighbour_height >= current || config.is_wall(neighbour_height) {
+                            continue;
+                        }
+
+                        (ny * width + nx) as u32
+                    }
+                    None => OUTSIDE,
+                };
+
+                if exit_height(exit) < exit_height(exits[root]) {
+                    exits[root] = exit;
+                }
+            }
+        }
+    }
+
+    // follow exits down to sinks, remembering results for every visited plateau root
+    let mut sinks = vec![NO_BASIN; width * height];
+    let mut resolved = vec![false; width * height];
+    let mut path = Vec::new();
+
+    for cell in 0..width * height {
+        let (x, y) = (cell % width, cell / width);
+        if config.is_wall(cell_height(x, y)) {
+            continue;
+        }
+
+        let mut root = union_find.find(cell as u32);
+        let sink = loop {
+            if resolved[root as usize] {
+                break sinks[root as usize];
+            }
+
+            path.push(root);
+            match exits[root as usize] {
+                SINK => break root,
+                OUTSIDE => break NO_BASIN,
+                lower => root = union_find.find(lower),
+            }
+        };
+
+        for root in path.drain(..) {
+            sinks[root as usize] = sink;
+            resolved[root as usize] = true;
+        }
+        sinks[cell] = sink;
+    }
+
+    sinks
+}
@@ -0,0 +1,101 @@
+/// Which cells are neighbours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// only cells, sharing a side
+    Four,
+    /// cells, sharing a side or a corner
+    Eight,
+}
+
+/// What lies beyond the edges of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edges {
+    /// a wall, higher than any cell
+    Walled,
+    /// open space, lower than any cell, so water drains off the map
+    Open,
+    /// the opposite edge of the map
+    Wrap,
+}
+
+/// How basins are found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// basins are connected areas, surrounded by walls
+    Walls,
+    /// basin of a cell is the low point, to which water flows from it
+    Watershed,
+}
+
+/// Heightmap analysis parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config {
+    /// cells of this height or higher don't belong to any basin, `None` for no walls
+    pub wall_height: Option<u32>,
+    pub connectivity: Connectivity,
+    pub edges: Edges,
+    pub mode: Mode,
+}
+
+const FOUR_NEIGHBOURS: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+const EIGHT_NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+impl Default for Config {
+    /// Rules of the puzzle: walls of height 9, four neighbours and walled edges
+    fn default() -> Self {
+        Self {
+            wall_height: Some(9),
+            connectivity: Connectivity::Four,
+            edges: Edges::Walled,
+            mode: Mode::Walls,
+        }
+    }
+}
+
+impl Config {
+    pub(crate) fn is_wall(&self, height: u32) -> bool {
+        self.wall_height
+            .is_some_and(|wall_height| height >= wall_height)
+    }
+
+    /// Neighbours of the cell on `width`×`height` map, `None` stands for open space
+    /// beyond the edge, walled edges are skipped
+    pub(crate) fn neighbours(
+        &self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+    ) -> impl Iterator<Item = Option<(usize, usize)>> + '_ {
+        let offsets: &[_] = match self.connectivity {
+            Connectivity::Four => &FOUR_NEIGHBOURS,
+            Connectivity::Eight => &EIGHT_NEIGHBOURS,
+        };
+        let (w, h) = (width as isize, height as isize);
+
+        offsets.iter().filter_map(move |&(dx, dy)| {
+            let (nx, ny) = (x as isize + dx, y as isize + dy);
+
+            if (0..w).contains(&nx) && (0..h).contains(&ny) {
+                return Some(Some((nx as usize, ny as usize)));
+            }
+
+            match self.edges {
+                Edges::Walled => None,
+                Edges::Open => Some(None),
+                Edges::Wrap => {
+                    let wrapped = (nx.rem_euclid(w) as usize, ny.rem_euclid(h) as usize);
+
+                    (wrapped != (x, y)).then_some(Some(wrapped))
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,186 @@
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Integer type of cell heights
+pub trait Height: Copy + Ord + Into<u32> + TryFrom<u32> {}
+
+impl Height for u8 {}
+impl Height for u16 {}
+impl Height for u32 {}
+
+/// Heights of all cells, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heightmap<H> {
+    width: usize,
+    height: usize,
+    cells: Vec<H>,
+}
+
+/// Heightmap parsing error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ParseHeightmapError {
+    /// heightmap has no cells
+    Empty,
+    /// row {row} has {len} cells, while the first one has {width}
+    RaggedRow {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+    /// invalid digit {found:?} at row {row}, column {col}
+    InvalidDigit { row: usize, col: usize, found: char },
+    /// invalid height {token:?} at row {row}, column {col}
+    InvalidHeight {
+        row: usize,
+        col: usize,
+        token: String,
+    },
+    /// height {token} at row {row}, column {col} doesn't fit into the height type
+    TooHigh {
+        row: usize,
+        col: usize,
+        token: String,
+    },
+}
+
+impl<H: Height> Heightmap<H> {
+    /// Build heightmap from heights of all cells, row by row
+    pub fn new(width: usize, cells: Vec<H>) -> Result<Self, ParseHeightmapError> {
+        if width == 0 || cells.is_empty() {
+            return Err(ParseHeightmapError::Empty);
+        }
+
+        let height = cells.len() / width;
+        if !cells.len().is_multiple_of(width) {
+            return Err(ParseHeightmapError::RaggedRow {
+                row: height,
+                len: cells.len() % width,
+                width,
+            });
+        }
+
+        Ok(Self {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Parse rows of whitespace separated integer heights
+    pub fn parse_integers(s: &str) -> Result<Self, ParseHeightmapError> {
+        Self::parse_rows(s, str::split_ascii_whitespace, |row, col, token| {
+            let height = parse_height(row, col, token)?;
+
+            H::try_from(height).map_err(|_| ParseHeightmapError::TooHigh {
+                row,
+                col,
+                token: token.to_owned(),
+            })
+        })
+    }
+
+    /// Parse non-empty lines into rows, checking that all of them have the same length
+    fn parse_rows<'a, I>(
+        s: &'a str,
+        split_row: impl Fn(&'a str) -> I,
+        parse_cell: impl Fn(usize, usize, I::Item) -> Result<H, ParseHeightmapError>,
+    ) -> Result<Self, ParseHeightmapError>
+    where
+        I: Iterator,
+    {
+        let mut cells = Vec::with_capacity(expected_cells(s, &split_row));
+        let width = visit_rows(s, split_row, |row, col, token| {
+            cells.push(parse_cell(row, col, token)?);
+            Ok(())
+        })?;
+
+        Self::new(width, cells)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> H {
+        self.cells[y * self.width + x]
+    }
+
+    /// Rows of cells from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[H]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl Heightmap<u8> {
+    /// Parse rows of single digit heights, as in the puzzle input
+    pub fn parse_digits(s: &str) -> Result<Self, ParseHeightmapError> {
+        Self::parse_rows(s, str::chars, |row, col, c| {
+            c.to_digit(10)
+                .map(|digit| digit as u8)
+                .ok_or(ParseHeightmapError::InvalidDigit { row, col, found: c })
+        })
+    }
+}
+
+/// Non-empty lines of the map
+fn lines(s: &str) -> impl Iterator<Item = &str> {
+    s.lines().map(str::trim).filter(|line| !line.is_empty())
+}
+
+/// Number of cells, if all rows are as long as the first one, every cell takes at least a byte
+pub(crate) fn expected_cells<'a, I: Iterator>(
+    s: &'a str,
+    split_row: impl Fn(&'a str) -> I,
+) -> usize {
+    let mut lines = lines(s);
+    let cells = lines
+        .next()
+        .map_or(0, |first| split_row(first).count() * (1 + lines.count()));
+
+    cells.min(s.len())
+}
+
+/// Visit every cell of non-empty lines with its row and column, checking that all rows
+/// have the same length, and return the width
+pub(crate) fn visit_rows<'a, I: Iterator>(
+    s: &'a str,
+    split_row: impl Fn(&'a str) -> I,
+    mut visit: impl FnMut(usize, usize, I::Item) -> Result<(), ParseHeightmapError>,
+) -> Result<usize, ParseHeightmapError> {
+    let mut width = 0;
+
+    for (row, line) in lines(s).enumerate() {
+        let mut len = 0;
+        for (col, cell) in split_row(line).enumerate() {
+            visit(row, col, cell)?;
+            len += 1;
+        }
+
+        if row == 0 {
+            width = len;
+        } else if len != width {
+            return Err(ParseHeightmapError::RaggedRow { row, len, width });
+        }
+    }
+
+    Ok(width)
+}
+
+/// Parse a single integer height
+pub(crate) fn parse_height(
+    row: usize,
+    col: usize,
+    token: &str,
+) -> Result<u32, ParseHeightmapError> {
+    token
+        .parse()
+        .map_err(|_| ParseHeightmapError::InvalidHeight {
+            row,
+            col,
+            token: token.to_owned(),
+        })
+}
@@ -1,91 +1,190 @@
-use std::{collections::BTreeSet, error::Error, iter::once, str::FromStr};
+use std::{error::Error, str::FromStr};
 
 use advent_utils::{Part, Solver};
 
+pub use basins::{Basin, BasinMap};
+pub use config::{Config, Connectivity, Edges, Mode};
+pub use heightmap::{Height, Heightmap, ParseHeightmapError};
+
+mod basins;
+mod config;
+mod heightmap;
+
+/// Heightmap, stored with the narrowest type, which fits all heights
+#[derive(Debug)]
+enum Heights {
+    U8(Heightmap<u8>),
+    U16(Heightmap<u16>),
+    U32(Heightmap<u32>),
+}
+
+impl Heights {
+    fn size(&self) -> (usize, usize) {
+        match self {
+            Self::U8(heightmap) => (heightmap.width(), heightmap.height()),
+            Self::U16(heightmap) => (heightmap.width(), heightmap.height()),
+            Self::U32(heightmap) => (heightmap.width(), heightmap.height()),
+        }
+    }
+
+    fn get(&self, x: usize, y: usize) -> u32 {
+        match self {
+            Self::U8(heightmap) => heightmap.get(x, y).into(),
+            Self::U16(heightmap) => heightmap.get(x, y).into(),
+            Self::U32(heightmap) => heightmap.get(x, y),
+        }
+    }
+
+    /// Parse whitespace separated heights in a single pass, widening the type of all cells
+    /// only when a height doesn't fit
+    fn parse_integers(s: &str) -> Result<Self, ParseHeightmapError> {
+        let split_row = str::split_ascii_whitespace;
+        let mut cells = Cells::U8(Vec::with_capacity(heightmap::expected_cells(s, split_row)));
+        let width = heightmap::visit_rows(s, split_row, |row, col, token| {
+            cells.push(heightmap::parse_height(row, col, token)?);
+            Ok(())
+        })?;
+
+        Ok(match cells {
+            Cells::U8(cells) => Self::U8(Heightmap::new(width, cells)?),
+            Cells::U16(cells) => Self::U16(Heightmap::new(width, cells)?),
+            Cells::U32(cells) => Self::U32(Heightmap::new(width, cells)?),
+        })
+    }
+}
+
+/// Heights, parsed so far, stored with the narrowest type, which fits all of them
+enum Cells {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Cells {
+    fn push(&mut self, height: u32) {
+        match self {
+            Self::U8(cells) => match u8::try_from(height) {
+                Ok(height) => cells.push(height),
+                Err(_) => {
+                    *self = Self::U16(widen(std::mem::take(cells)));
+                    self.push(height);
+                }
+            },
+            Self::U16(cells) => match u16::try_from(height) {
+                Ok(height) => cells.push(height),
+                Err(_) => {
+                    *self = Self::U32(widen(std::mem::take(cells)));
+                    self.push(height);
+                }
+            },
+            Self::U32(cells) => cells.push(height),
+        }
+    }
+}
+
+/// The same cells with a wider type, keeping the reserved capacity
+fn widen<T, W: From<T>>(cells: Vec<T>) -> Vec<W> {
+    let mut wide = Vec::with_capacity(cells.capacity());
+    wide.extend(cells.into_iter().map(W::from));
+
+    wide
+}
+
 #[derive(Debug)]
 pub struct Solution {
-    heights: Vec<Vec<u8>>,
+    heights: Heights,
+    config: Config,
 }
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
+    /// Rows of digits, as in the puzzle
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut heights: Vec<Vec<u8>> = s
-            .lines()
-            .map(|line| {
-                once(u8::MAX)
-                    .chain(line.bytes().map(|b| b - b'0'))
-                    .chain(once(u8::MAX))
-                    .collect()
-            })
-            .collect();
+        Ok(Heightmap::parse_digits(s)?.into())
+    }
+}
+
+macro_rules! impl_from_heightmap {
+    ($($height:ty => $variant:ident),*) => {
+        $(
+            impl From<Heightmap<$height>> for Solution {
+                fn from(heightmap: Heightmap<$height>) -> Self {
+                    Self {
+                        heights: Heights::$variant(heightmap),
+                        config: Config::default(),
+                    }
+                }
+            }
+        )*
+    };
+}
 
-        let line_size = heights[0].len();
+impl_from_heightmap!(u8 => U8, u16 => U16, u32 => U32);
 
-        heights.insert(0, vec![u8::MAX; line_size]);
-        heights.push(vec![u8::MAX; line_size]);
+impl Solution {
+    /// Parse rows of whitespace separated heights, stored with the narrowest type, which fits
+    /// all of them
+    pub fn parse_integers(s: &str) -> Result<Self, ParseHeightmapError> {
+        Ok(Self {
+            heights: Heights::parse_integers(s)?,
+            config: Config::default(),
+        })
+    }
 
-        Ok(Self { heights })
+    /// Replace puzzle rules with custom analysis parameters
+    pub fn with_config(self, config: Config) -> Self {
+        Self { config, ..self }
     }
-}
 
-fn find_basin_size(heights: &[Vec<u8>], x: usize, y: usize) -> usize {
-    let mut to_visit_queue = vec![(x, y, heights[y][x])];
-    let mut set = BTreeSet::new();
-    set.insert((x, y));
+    /// Cells, lower than all of their neighbours, as `(x, y)`
+    pub fn low_points(&self) -> Vec<(usize, usize)> {
+        let (width, height) = self.heights.size();
+        let mut low_points = vec![];
 
-    while let Some((x, y, current)) = to_visit_queue.pop() {
-        for coord in [(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)] {
-            let value = heights[coord.1][coord.0];
+        for y in 0..height {
+            for x in 0..width {
+                let current = self.heights.get(x, y);
+                let is_low = self
+                    .config
+                    .neighbours((x, y), (width, height))
+                    .all(|neighbour| {
+                        neighbour.is_some_and(|(nx, ny)| current < self.heights.get(nx, ny))
+                    });
 
-            if value > current && set.insert(coord) && value < 9 {
-                to_visit_queue.push((coord.0, coord.1, value))
+                if is_low {
+                    low_points.push((x, y));
+                }
             }
         }
+
+        low_points
     }
 
-    set.len()
+    /// Label every non-wall cell with its basin
+    pub fn basins(&self) -> BasinMap {
+        let (width, height) = self.heights.size();
+
+        BasinMap::new(width, height, |x, y| self.heights.get(x, y), &self.config)
+    }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
-        let mut low_points = vec![];
-
-        for (y, rows_window) in self.heights.windows(3).enumerate() {
-            for (x, row) in rows_window[1].windows(3).enumerate() {
-                if row[1] < row[0]
-                    && row[1] < row[2]
-                    && row[1] < rows_window[0][x + 1]
-                    && row[1] < rows_window[2][x + 1]
-                {
-                    low_points.push((x + 1, y + 1));
-                }
-            }
-        }
-
         match part {
             Part::One => {
                 format!(
                     "sum of risk values is {}",
-                    low_points
+                    self.low_points()
                         .iter()
-                        .map(|&(x, y)| self.heights[y][x] as u32)
-                        .sum::<u32>()
-                        + low_points.len() as u32
+                        .map(|&(x, y)| u64::from(self.heights.get(x, y)) + 1)
+                        .sum::<u64>()
                 )
             }
             Part::Two => {
-                let mut basin_sizes = low_points
-                    .iter()
-                    .map(|&(x, y)| find_basin_size(&self.heights, x, y))
-                    .collect::<Vec<_>>();
-
-                basin_sizes.sort_unstable();
-
                 format!(
                     "product of basin sizes is {}",
-                    basin_sizes.into_iter().rev().take(3).product::<usize>()
+                    self.basins().largest_sizes(3).iter().product::<usize>()
                 )
             }
         }
@@ -95,3 +194,246 @@ impl Solver for Solution {
         9
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_example() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+
+        assert_eq!(solution.solve(Part::One), "sum of risk values is 15");
+        assert_eq!(solution.solve(Part::Two), "product of basin sizes is 1134");
+    }
+
+    #[test]
+    fn test_basin_map() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        let basins = solution.basins();
+
+        assert_eq!(
+            basins.basins(),
+            [
+                Basin {
+                    low_point: (1, 0),
+                    low_height: 1,
+                    size: 3,
+                    depth: 2,
+                },
+                Basin {
+                    low_point: (9, 0),
+                    low_height: 0,
+                    size: 9,
+                    depth: 4,
+                },
+                Basin {
+                    low_point: (2, 2),
+                    low_height: 5,
+                    size: 14,
+                    depth: 3,
+                },
+                Basin {
+                    low_point: (6, 4),
+                    low_height: 5,
+                    size: 9,
+                    depth: 3,
+                },
+            ]
+        );
+        assert_eq!(basins.label(0, 0), Some(0));
+        assert_eq!(basins.label(2, 0), None);
+        assert_eq!(basins.label(6, 1), Some(1));
+        assert_eq!(basins.label(0, 3), Some(2));
+        assert_eq!(basins.label(9, 4), Some(3));
+    }
+
+    #[test]
+    fn test_plateaus() {
+        // flat bottom without a strict low point, reachable only through equal heights
+        let solution: Solution = "55595\n55596\n99999\n12329".parse().unwrap();
+        let basins = solution.basins();
+
+        assert_eq!(solution.solve(Part::One), "sum of risk values is 11");
+        assert_eq!(basins.largest_sizes(5), [6, 4, 2]);
+        assert_eq!(basins.basins()[0].low_point, (0, 0));
+        assert_eq!(basins.basins()[0].depth, 0);
+        assert_eq!(basins.label(4, 0), Some(1));
+        assert_eq!(basins.basins()[1].depth, 1);
+    }
+
+    #[test]
+    fn test_configuration() {
+        let heightmap = "4449\n3194\n9924\n1999";
+        let solution: Solution = heightmap.parse().unwrap();
+
+        assert_eq!(solution.low_points(), [(1, 1), (2, 2), (0, 3)]);
+        assert_eq!(solution.basins().largest_sizes(5), [5, 3, 1]);
+
+        // `9`s are no longer walls, so everything is a single basin
+        let no_walls = Config {
+            wall_height: None,
+            ..Config::default()
+        };
+        let solution: Solution = heightmap.parse::<Solution>().unwrap().with_config(no_walls);
+        assert_eq!(solution.basins().largest_sizes(5), [16]);
+
+        // `2` touches `1` through a diagonal, so it's not a low point anymore
+        let diagonals = Config {
+            connectivity: Connectivity::Eight,
+            ..Config::default()
+        };
+        let solution: Solution = heightmap
+            .parse::<Solution>()
+            .unwrap()
+            .with_config(diagonals);
+        assert_eq!(solution.low_points(), [(1, 1), (0, 3)]);
+        assert_eq!(solution.basins().largest_sizes(5), [8, 1]);
+
+        // water flows off the map, so border cells are never low points
+        let open = Config {
+            edges: Edges::Open,
+            ..Config::default()
+        };
+        let solution: Solution = heightmap.parse::<Solution>().unwrap().with_config(open);
+        assert_eq!(solution.low_points(), [(1, 1), (2, 2)]);
+
+        // on a torus the bottom left `1` is next to the top left `4`,
+        // and the right area is next to the left one
+        let torus = Config {
+            edges: Edges::Wrap,
+            ..Config::default()
+        };
+        let solution: Solution = heightmap.parse::<Solution>().unwrap().with_config(torus);
+        assert_eq!(solution.low_points(), [(1, 1), (2, 2), (0, 3)]);
+        assert_eq!(solution.basins().largest_sizes(5), [9]);
+    }
+
+    #[test]
+    fn test_watershed() {
+        // two valleys with a ridge of `5`s, a plateau of `3`s drains to the right one
+        let heightmap = "1234543\n2345532\n3456331";
+        let watershed = Config {
+            wall_height: None,
+            mode: Mode::Watershed,
+            ..Config::default()
+        };
+        let solution: Solution = heightmap
+            .parse::<Solution>()
+            .unwrap()
+            .with_config(watershed);
+        let basins = solution.basins();
+
+        assert_eq!(basins.basins().len(), 2);
+        assert_eq!(basins.basins()[0].low_point, (0, 0));
+        assert_eq!(basins.basins()[1].low_point, (6, 2));
+        assert_eq!(basins.label(4, 2), Some(1));
+        assert_eq!(basins.label(3, 2), Some(1));
+        assert_eq!(basins.label(2, 2), Some(0));
+        assert_eq!(basins.basins()[0].size + basins.basins()[1].size, 21);
+
+        // with open edges every cell on the border drains off the map
+        let open = Config {
+            edges: Edges::Open,
+            ..watershed
+        };
+        let solution: Solution = heightmap.parse::<Solution>().unwrap().with_config(open);
+        assert!(solution.basins().basins().is_empty());
+        assert_eq!(solution.basins().label(3, 1), None);
+
+        // the AoC puzzle rules give the same basins in both modes
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        let watershed = Config {
+            mode: Mode::Watershed,
+            ..Config::default()
+        };
+        assert_eq!(
+            solution.basins().basins(),
+            solution.with_config(watershed).basins().basins()
+        );
+    }
+
+    #[test]
+    fn test_heightmap_formats() {
+        let digits: Solution = include_str!("../short.txt").parse().unwrap();
+        let integers = include_str!("../short.txt")
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| ((c as u32 - '0' as u32) * 1000).to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let config = Config {
+            wall_height: Some(9000),
+            ..Config::default()
+        };
+        let integers = Solution::parse_integers(&integers)
+            .unwrap()
+            .with_config(config);
+
+        assert!(matches!(integers.heights, Heights::U16(_)));
+        assert_eq!(integers.low_points(), digits.low_points());
+        assert_eq!(integers.solve(Part::Two), digits.solve(Part::Two));
+        assert_eq!(integers.basins().basins()[1].depth, 4000);
+
+        let small = Solution::parse_integers("1 2 3\n4 5 6\n").unwrap();
+        assert!(matches!(small.heights, Heights::U8(_)));
+        let large = Solution::parse_integers("1 2 70000\n4 5 6").unwrap();
+        assert!(matches!(large.heights, Heights::U32(_)));
+        assert_eq!(large.solve(Part::One), "sum of risk values is 2");
+
+        assert_eq!(
+            Heightmap::parse_digits("123\n4x6"),
+            Err(ParseHeightmapError::InvalidDigit {
+                row: 1,
+                col: 1,
+                found: 'x'
+            })
+        );
+        assert_eq!(
+            Heightmap::<u16>::parse_integers("1 2\n3"),
+            Err(ParseHeightmapError::RaggedRow {
+                row: 1,
+                len: 1,
+                width: 2
+            })
+        );
+        assert_eq!(
+            Heightmap::<u8>::parse_integers("1 256"),
+            Err(ParseHeightmapError::TooHigh {
+                row: 0,
+                col: 1,
+                token: "256".to_owned()
+            })
+        );
+        // a single column of integers isn't mistaken for rows of digits
+        let column = Solution::parse_integers("10\n20").unwrap();
+        assert_eq!(column.heights.size(), (1, 2));
+        assert_eq!(column.low_points(), [(0, 0)]);
+        assert_eq!("10\n20".parse::<Solution>().unwrap().heights.size(), (2, 2));
+
+        let widened = Solution::parse_integers("1 255\n256 2\n65536 3").unwrap();
+        assert!(matches!(widened.heights, Heights::U32(_)));
+        assert_eq!(widened.heights.get(0, 1), 256);
+        assert_eq!(widened.heights.get(0, 2), 65536);
+        assert_eq!(
+            Solution::parse_integers("1 2\n3").unwrap_err(),
+            ParseHeightmapError::RaggedRow {
+                row: 1,
+                len: 1,
+                width: 2
+            }
+        );
+
+        assert!(Solution::parse_integers("1 -2").is_err());
+        assert!("1 2".parse::<Solution>().is_err());
+        assert!("".parse::<Solution>().is_err());
+
+        let heightmap = Heightmap::new(2, vec![3u32, 1, 2, 5]).unwrap();
+        assert_eq!(heightmap.rows().collect::<Vec<_>>(), [[3, 1], [2, 5]]);
+        assert_eq!(Solution::from(heightmap).low_points(), [(1, 0), (0, 1)]);
+    }
+}
@@ -1,9 +1,20 @@
-use std::error::Error;
+use std::{env::var, error::Error, fs::read_to_string};
 
+use advent_input::input_path;
 use advent_utils::Solver;
 
 use day_09::Solution;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    Solution::solve_env_config()
+    // `INTEGERS=1` reads rows of whitespace separated heights instead of rows of digits
+    if var("INTEGERS").is_err() {
+        return Solution::solve_env_config();
+    }
+
+    let solution = Solution::parse_integers(&read_to_string(input_path())?)?;
+    for part in Solution::implemented_parts() {
+        println!("{}", solution.solve(part));
+    }
+
+    Ok(())
 }
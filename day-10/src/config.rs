@@ -0,0 +1,133 @@
+use displaydoc::Display;
+use thiserror::Error;
+
+use crate::diagnostic::Diagnostic;
+
+/// Kind of brackets with its scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BracketPair {
+    pub open: char,
+    pub close: char,
+    /// score of an illegal closing bracket of this kind
+    pub corruption_score: u64,
+    /// score of a closing bracket of this kind, added to complete a line
+    pub completion_score: u64,
+}
+
+/// Result of checking a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Score {
+    Ok,
+    Incomplete(u64),
+    /// the line is incomplete, but its completion score doesn't fit into u64
+    IncompleteOverflow,
+    Corrupted(u64),
+    /// the line contains a character, which isn't a bracket
+    UnknownCharacter(char),
+}
+
+/// What to do with characters, which aren't brackets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownCharacters {
+    Ignore,
+    Error,
+}
+
+/// Configuration error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    /// no bracket pairs
+    NoPairs,
+    /// character {0:?} is used in several bracket pairs
+    DuplicateCharacter(char),
+}
+
+/// Bracket pairs and scoring rules
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pairs: Vec<BracketPair>,
+    /// completion score is multiplied by this before adding each next bracket
+    completion_multiplier: u64,
+    unknown_characters: UnknownCharacters,
+}
+
+impl Default for Config {
+    /// Rules of the puzzle
+    fn default() -> Self {
+        let pair = |open, close, corruption_score, completion_score| BracketPair {
+            open,
+            close,
+            corruption_score,
+            completion_score,
+        };
+
+        Self {
+            pairs: vec![
+                pair('(', ')', 3, 1),
+                pair('[', ']', 57, 2),
+                pair('{', '}', 1197, 3),
+                pair('<', '>', 25137, 4),
+            ],
+            completion_multiplier: 5,
+            unknown_characters: UnknownCharacters::Error,
+        }
+    }
+}
+
+impl Config {
+    pub fn new(pairs: Vec<BracketPair>, completion_multiplier: u64) -> Result<Self, ConfigError> {
+        if pairs.is_empty() {
+            return Err(ConfigError::NoPairs);
+        }
+
+        let mut seen = Vec::with_capacity(pairs.len() * 2);
+        for pair in &pairs {
+            for c in [pair.open, pair.close] {
+                if seen.contains(&c) {
+                    return Err(ConfigError::DuplicateCharacter(c));
+                }
+                seen.push(c);
+            }
+        }
+
+        Ok(Self {
+            pairs,
+            completion_multiplier,
+            unknown_characters: UnknownCharacters::Error,
+        })
+    }
+
+    pub fn with_unknown_characters(self, unknown_characters: UnknownCharacters) -> Self {
+        Self {
+            unknown_characters,
+            ..self
+        }
+    }
+
+    pub fn pairs(&self) -> &[BracketPair] {
+        &self.pairs
+    }
+
+    pub fn completion_multiplier(&self) -> u64 {
+        self.completion_multiplier
+    }
+
+    pub fn unknown_characters(&self) -> UnknownCharacters {
+        self.unknown_characters
+    }
+
+    /// Pair, opened by the character
+    pub fn opened_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.open == c)
+    }
+
+    /// Pair, closed by the character
+    pub fn closed_by(&self, c: char) -> Option<&BracketPair> {
+        self.pairs.iter().find(|pair| pair.close == c)
+    }
+
+    /// Check the line, scoring either its first illegal character or its completion
+    pub fn score(&self, line: &str) -> Score {
+        Diagnostic::new(line, self).score()
+    }
+}
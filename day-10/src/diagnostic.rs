@@ -0,0 +1,130 @@
+use std::fmt::Write;
+
+use crate::config::{Config, Score, UnknownCharacters};
+
+/// Result of checking a line, with everything needed to point at the problem
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    Ok,
+    /// all brackets match, but some are left open
+    Incomplete {
+        /// closing brackets, which complete the line
+        completion: String,
+        /// score of the completion, `None` if it doesn't fit into u64
+        score: Option<u64>,
+    },
+    /// a closing bracket doesn't match the last open one
+    Corrupted {
+        /// byte offset of the illegal character
+        offset: usize,
+        /// closer of the last open bracket, `None` if nothing is open
+        expected: Option<char>,
+        found: char,
+        /// corruption score of the illegal character
+        score: u64,
+    },
+    /// a character, which isn't a bracket
+    UnknownCharacter {
+        offset: usize,
+        found: char,
+    },
+}
+
+impl Diagnostic {
+    /// Check the line, stopping at its first illegal character
+    pub fn new(line: &str, config: &Config) -> Self {
+        let mut stack = vec![];
+
+        for (offset, c) in line.char_indices() {
+            if let Some(pair) = config.opened_by(c) {
+                stack.push(pair);
+            } else if let Some(pair) = config.closed_by(c) {
+                let expected = stack.pop().map(|open| open.close);
+                if expected != Some(c) {
+                    return Self::Corrupted {
+                        offset,
+                        expected,
+                        found: c,
+                        score: pair.corruption_score,
+                    };
+                }
+            } else if config.unknown_characters() == UnknownCharacters::Error {
+                return Self::UnknownCharacter { offset, found: c };
+            }
+        }
+
+        if stack.is_empty() {
+            return Self::Ok;
+        }
+
+        Self::Incomplete {
+            completion: stack.iter().rev().map(|pair| pair.close).collect(),
+            score: stack.iter().rev().try_fold(0, |score: u64, pair| {
+                score
+                    .checked_mul(config.completion_multiplier())?
+                    .checked_add(pair.completion_score)
+            }),
+        }
+    }
+
+    /// Byte offset of the problem, the end of the line for incomplete ones
+    pub fn offset(&self, line: &str) -> Option<usize> {
+        match self {
+            Self::Ok => None,
+            Self::Incomplete { .. } => Some(line.len()),
+            Self::Corrupted { offset, .. } | Self::UnknownCharacter { offset, .. } => Some(*offset),
+        }
+    }
+
+    /// Score, as defined by the config, which the line was checked with
+    pub fn score(&self) -> Score {
+        match self {
+            Self::Ok => Score::Ok,
+            Self::Incomplete { score, .. } => {
+                score.map_or(Score::IncompleteOverflow, Score::Incomplete)
+            }
+            Self::Corrupted { score, .. } => Score::Corrupted(*score),
+            Self::UnknownCharacter { found, .. } => Score::UnknownCharacter(*found),
+        }
+    }
+
+    /// Short description of the problem
+    pub fn message(&self) -> Option<String> {
+        match self {
+            Self::Ok => None,
+            Self::Incomplete { completion, .. } => {
+                Some(format!("incomplete, add {:?}", completion))
+            }
+            Self::Corrupted {
+                expected: Some(expected),
+                found,
+                ..
+            } => Some(format!("expected {:?}, found {:?}", expected, found)),
+            Self::Corrupted {
+                expected: None,
+                found,
+                ..
+            } => Some(format!("unexpected {:?}, nothing is open", found)),
+            Self::UnknownCharacter { found, .. } => Some(format!("unknown character {:?}", found)),
+        }
+    }
+
+    /// The line with a caret under the problem and the message next to it,
+    /// `None` for correct lines
+    pub fn render(&self, line: &str) -> Option<String> {
+        let offset = self.offset(line)?;
+        let column = line[..offset].chars().count();
+
+        let mut rendered = String::new();
+        writeln!(rendered, "{}", line).expect("writing to string never fails");
+        write!(
+            rendered,
+            "{}^ {}",
+            " ".repeat(column),
+            self.message().unwrap_or_default()
+        )
+        .expect("writing to string never fails");
+
+        Some(rendered)
+    }
+}
@@ -2,78 +2,105 @@ use std::{error::Error, str::FromStr};
 
 use advent_utils::{Part, Solver};
 
+pub use config::{BracketPair, Config, ConfigError, Score, UnknownCharacters};
+pub use diagnostic::Diagnostic;
+pub use repair::{repair, Edit, Repair};
+
+mod config;
+mod diagnostic;
+mod repair;
+
 #[derive(Debug)]
 pub struct Solution {
-    line_scores: Vec<Score>,
+    lines: Vec<String>,
+    config: Config,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self {
-            line_scores: s.lines().map(calc_line_score).collect(),
-        })
-    }
-}
+        let lines = s.lines().map(str::to_owned).collect::<Vec<_>>();
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Score {
-    Ok,
-    Incomplete(u64),
-    Corrupted(u64),
+        Ok(Self::new(lines, Config::default()))
+    }
 }
 
-fn calc_line_score(line: &str) -> Score {
-    let mut stack = vec![];
-
-    for c in line.bytes() {
-        match c {
-            b'<' | b'(' | b'{' | b'[' => stack.push(c),
-            b'>' => match stack.pop() {
-                Some(b'<') => {}
-                _ => return Score::Corrupted(25137),
-            },
-            b'}' => match stack.pop() {
-                Some(b'{') => {}
-                _ => return Score::Corrupted(1197),
-            },
-            b']' => match stack.pop() {
-                Some(b'[') => {}
-                _ => return Score::Corrupted(57),
-            },
-            b')' => match stack.pop() {
-                Some(b'(') => {}
-                _ => return Score::Corrupted(3),
-            },
-            _ => unreachable!(),
+impl Solution {
+    fn new(lines: Vec<String>, config: Config) -> Self {
+        Self {
+            diagnostics: lines
+                .iter()
+                .map(|line| Diagnostic::new(line, &config))
+                .collect(),
+            lines,
+            config,
         }
     }
 
-    if stack.is_empty() {
-        Score::Ok
-    } else {
-        Score::Incomplete(stack.iter().rev().fold(0, |score, c| {
-            score * 5
-                + match c {
-                    b'(' => 1,
-                    b'[' => 2,
-                    b'{' => 3,
-                    b'<' => 4,
-                    _ => unreachable!(),
-                }
-        }))
+    /// Replace puzzle brackets and scores with custom ones, rechecking all lines
+    pub fn with_config(self, config: Config) -> Self {
+        Self::new(self.lines, config)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Result of checking every line, in input order
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Score of every line, in input order
+    pub fn line_scores(&self) -> impl Iterator<Item = Score> + '_ {
+        self.diagnostics.iter().map(Diagnostic::score)
+    }
+
+    /// The first character, which isn't a bracket, if such characters are errors
+    fn unknown_character(&self) -> Option<char> {
+        self.diagnostics
+            .iter()
+            .find_map(|diagnostic| match diagnostic {
+                Diagnostic::UnknownCharacter { found, .. } => Some(*found),
+                _ => None,
+            })
+    }
+
+    /// Minimal repair of every line, in input order
+    pub fn repairs(&self) -> Vec<Repair> {
+        self.lines
+            .iter()
+            .map(|line| repair(line, &self.config))
+            .collect()
+    }
+
+    /// Every problematic line with its number and a caret under the problem
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+
+        for (number, (line, diagnostic)) in self.lines.iter().zip(&self.diagnostics).enumerate() {
+            if let Some(rendered) = diagnostic.render(line) {
+                report.push_str(&format!("line {}:\n{}\n", number + 1, rendered));
+            }
+        }
+
+        report
     }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
+        if let Some(c) = self.unknown_character() {
+            return format!("unknown character {:?}", c);
+        }
+
         match part {
             Part::One => {
                 format!(
                     "total corruptness score is {}",
-                    self.line_scores
-                        .iter()
+                    self.line_scores()
                         .filter_map(|score| match score {
                             Score::Corrupted(s) => Some(s),
                             _ => None,
@@ -82,14 +109,23 @@ impl Solver for Solution {
                 )
             }
             Part::Two => {
-                let mut autocomplete_scores = self
-                    .line_scores
-                    .iter()
-                    .filter_map(|score| match score {
-                        Score::Incomplete(s) => Some(*s),
-                        _ => None,
-                    })
-                    .collect::<Vec<_>>();
+                let mut autocomplete_scores = vec![];
+                for (number, score) in self.line_scores().enumerate() {
+                    match score {
+                        Score::Incomplete(s) => autocomplete_scores.push(s),
+                        Score::IncompleteOverflow => {
+                            return format!(
+                                "completion score of line {} doesn't fit into u64",
+                                number + 1
+                            )
+                        }
+                        _ => {}
+                    }
+                }
+
+                if autocomplete_scores.is_empty() {
+                    return "no incomplete lines".to_owned();
+                }
 
                 autocomplete_scores.sort_unstable();
 
@@ -116,7 +152,7 @@ mod tests {
 
         assert_eq!(
             lines
-                .filter_map(|line| match calc_line_score(line) {
+                .filter_map(|line| match Config::default().score(line) {
                     Score::Corrupted(s) => Some(s),
                     _ => None,
                 })
@@ -129,7 +165,7 @@ mod tests {
     fn test_smallest_example_part_two() {
         let line = "<{([{{}}[<[[[<>{}]]]>[]]";
 
-        assert_eq!(calc_line_score(line), Score::Incomplete(294))
+        assert_eq!(Config::default().score(line), Score::Incomplete(294))
     }
 
     #[test]
@@ -137,7 +173,7 @@ mod tests {
         let lines = include_str!("../short.txt").split('\n');
 
         let autocomplete_scores = lines
-            .filter_map(|line| match calc_line_score(line) {
+            .filter_map(|line| match Config::default().score(line) {
                 Score::Incomplete(s) => Some(s),
                 _ => None,
             })
@@ -148,4 +184,262 @@ mod tests {
             vec![288957, 5566, 1480781, 995444, 294]
         )
     }
+
+    #[test]
+    fn test_custom_config() {
+        let pair = |open, close, corruption_score, completion_score| BracketPair {
+            open,
+            close,
+            corruption_score,
+            completion_score,
+        };
+        let config = Config::new(vec![pair('(', ')', 10, 1), pair('«', '»', 100, 2)], 3).unwrap();
+
+        assert_eq!(config.score("(«»)"), Score::Ok);
+        assert_eq!(config.score("(«)"), Score::Corrupted(10));
+        assert_eq!(config.score("«(»"), Score::Corrupted(100));
+        assert_eq!(config.score("(«("), Score::Incomplete((3 + 2) * 3 + 1));
+        // square brackets aren't brackets anymore
+        assert_eq!(config.score("([)"), Score::UnknownCharacter('['));
+
+        let solution: Solution = "(«\n(«)\n((".parse::<Solution>().unwrap();
+        assert_eq!(solution.solve(Part::One), "unknown character '«'");
+
+        let solution = solution.with_config(config);
+        assert_eq!(solution.solve(Part::One), "total corruptness score is 10");
+        assert_eq!(solution.solve(Part::Two), "winner is 7");
+
+        assert_eq!(Config::new(vec![], 5), Err(ConfigError::NoPairs));
+        assert_eq!(
+            Config::new(vec![pair('(', ')', 1, 1), pair('[', '(', 1, 1)], 5),
+            Err(ConfigError::DuplicateCharacter('('))
+        );
+    }
+
+    #[test]
+    fn test_completion_overflow() {
+        let config = Config::default();
+
+        assert_eq!(
+            config.score(&"<".repeat(27)),
+            Score::Incomplete(5u64.pow(27) - 1)
+        );
+        assert_eq!(config.score(&"<".repeat(28)), Score::IncompleteOverflow);
+
+        let solution: Solution = format!("<\n{}", "<".repeat(28)).parse().unwrap();
+        assert_eq!(
+            solution.solve(Part::Two),
+            "completion score of line 2 doesn't fit into u64"
+        );
+    }
+
+    #[test]
+    fn test_unknown_characters() {
+        let line = r#"{"key": ["value", <ref>]"#;
+
+        assert_eq!(Config::default().score(line), Score::UnknownCharacter('"'));
+
+        let config = Config::default().with_unknown_characters(UnknownCharacters::Ignore);
+        assert_eq!(config.score(line), Score::Incomplete(3));
+        assert_eq!(config.score(r#"{"a": [1, 2}"#), Score::Corrupted(1197));
+
+        let solution = include_str!("../short.txt")
+            .parse::<Solution>()
+            .unwrap()
+            .with_config(config);
+        assert_eq!(
+            solution.solve(Part::One),
+            "total corruptness score is 26397"
+        );
+        assert_eq!(solution.solve(Part::Two), "winner is 288957");
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        let diagnostics = solution.diagnostics();
+
+        assert_eq!(
+            diagnostics[0],
+            Diagnostic::Incomplete {
+                completion: "}}]])})]".to_owned(),
+                score: Some(288957)
+            }
+        );
+        assert_eq!(
+            diagnostics[2],
+            Diagnostic::Corrupted {
+                offset: 12,
+                expected: Some(']'),
+                found: '}',
+                score: 1197
+            }
+        );
+        assert_eq!(
+            diagnostics
+                .iter()
+                .filter_map(|diagnostic| match diagnostic {
+                    Diagnostic::Incomplete { completion, .. } => Some(completion.as_str()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            ["}}]])})]", ")}>]})", "}}>}>))))", "]]}}]}]}>", "])}>"]
+        );
+
+        assert_eq!(
+            Diagnostic::new("())", &Config::default()),
+            Diagnostic::Corrupted {
+                offset: 2,
+                expected: None,
+                found: ')',
+                score: 3
+            }
+        );
+        assert_eq!(Diagnostic::new("<[]>", &Config::default()), Diagnostic::Ok);
+
+        // offsets are in bytes, carets are placed by characters
+        let config = Config::default().with_unknown_characters(UnknownCharacters::Ignore);
+        let diagnostic = Diagnostic::new("(«»]", &config);
+        assert_eq!(diagnostic.offset("(«»]"), Some(5));
+        assert_eq!(
+            diagnostic.render("(«»]").unwrap(),
+            "(«»]\n   ^ expected ')', found ']'"
+        );
+    }
+
+    #[test]
+    fn test_report() {
+        let solution: Solution = "()\n{([)]\n[<>".parse().unwrap();
+
+        assert_eq!(
+            solution.report(),
+            "line 2:\n{([)]\n   ^ expected ']', found ')'\n\
+             line 3:\n[<>\n   ^ incomplete, add \"]\"\n"
+        );
+    }
+
+    #[test]
+    fn test_repair() {
+        let config = Config::default();
+
+        // a single wrong closer is substituted
+        let repaired = repair("{()()()>", &config);
+        assert_eq!(repaired.line, "{()()()}");
+        assert_eq!(
+            repaired.edits,
+            [Edit::Substitute {
+                offset: 7,
+                found: '>',
+                replacement: '}'
+            }]
+        );
+
+        // openers may become closers, if it's cheaper than closing them
+        let repaired = repair("{([(<{}[<>[]}>{[]{[(<()>", &config);
+        assert_eq!(repaired.cost(), 5);
+        assert_eq!(config.score(&repaired.line), Score::Ok);
+
+        let repaired = repair("[(])", &config);
+        assert_eq!(repaired.cost(), 2);
+        assert_eq!(config.score(&repaired.line), Score::Ok);
+
+        let repaired = repair("())", &config);
+        assert_eq!(repaired.line, "()");
+        assert_eq!(repaired.edits, [Edit::Delete { offset: 2, c: ')' }]);
+
+        let repaired = repair(")(", &config);
+        assert_eq!(repaired.line, "()");
+        assert_eq!(repaired.cost(), 2);
+
+        assert_eq!(repair("<[]>", &config).edits, []);
+
+        // completing a line is never cheaper than the minimal repair
+        let solution: Solution = include_str!("../short.txt").parse().unwrap();
+        for ((line, diagnostic), repaired) in solution
+            .lines
+            .iter()
+            .zip(solution.diagnostics())
+            .zip(solution.repairs())
+        {
+            assert_eq!(config.score(&repaired.line), Score::Ok);
+            if let Diagnostic::Incomplete { completion, .. } = diagnostic {
+                assert!(repaired.cost() <= completion.chars().count());
+                assert!(repaired.line.len() <= line.len() + completion.len());
+            }
+        }
+
+        // unknown characters are kept when ignored and deleted otherwise
+        let line = r#"{"a": [1, 2}, "b": 3"#;
+        let ignoring = Config::default().with_unknown_characters(UnknownCharacters::Ignore);
+        assert_eq!(repair(line, &ignoring).line, r#"{"a": [1, 2]}, "b": 3"#);
+        assert_eq!(repair("(a]", &config).line, "()");
+        assert_eq!(repair("(a]", &config).cost(), 2);
+    }
+
+    #[test]
+    fn test_repair_is_minimal() {
+        // compare with breadth first search over all strings, reachable with few edits
+        let config = Config::new(
+            vec![
+                BracketPair {
+                    open: '(',
+                    close: ')',
+                    corruption_score: 1,
+                    completion_score: 1,
+                },
+                BracketPair {
+                    open: '[',
+                    close: ']',
+                    corruption_score: 1,
+                    completion_score: 1,
+                },
+            ],
+            5,
+        )
+        .unwrap();
+        let alphabet = ['(', ')', '[', ']'];
+
+        let neighbours = |line: &String| {
+            let chars = line.chars().collect::<Vec<_>>();
+            let mut neighbours = vec![];
+            for i in 0..=chars.len() {
+                for c in alphabet {
+                    let mut inserted = chars.clone();
+                    inserted.insert(i, c);
+                    neighbours.push(inserted.into_iter().collect::<String>());
+                }
+                if i < chars.len() {
+                    let mut deleted = chars.clone();
+                    deleted.remove(i);
+                    neighbours.push(deleted.into_iter().collect());
+                    for c in alphabet {
+                        let mut substituted = chars.clone();
+                        substituted[i] = c;
+                        neighbours.push(substituted.into_iter().collect());
+                    }
+                }
+            }
+            neighbours
+        };
+        let distance = |line: &str| {
+            let mut layer = vec![line.to_owned()];
+            for distance in 0.. {
+                if layer.iter().any(|line| config.score(line) == Score::Ok) {
+                    return distance;
+                }
+                layer = layer.iter().flat_map(neighbours).collect();
+                layer.sort_unstable();
+                layer.dedup();
+            }
+            unreachable!()
+        };
+
+        for line in [
+            "(]", "([)]", "))((", "[(])", "(((", "]", "([]", "[)(]", "(]]",
+        ] {
+            let repaired = repair(line, &config);
+            assert_eq!(config.score(&repaired.line), Score::Ok, "{}", line);
+            assert_eq!(repaired.cost(), distance(line), "{}", line);
+        }
+    }
 }
@@ -1,9 +1,26 @@
-use std::error::Error;
+use std::{env::var, error::Error};
 
+use advent_input::read_input;
 use advent_utils::Solver;
 
 use day_10::Solution;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    Solution::solve_env_config()
+    // `LINT=1` prints every problematic line with a caret under the problem,
+    // `REPAIR=1` prints every line with the fewest edits, making it balanced
+    let (lint, repair) = (var("LINT").is_ok(), var("REPAIR").is_ok());
+    if !lint && !repair {
+        return Solution::solve_env_config();
+    }
+
+    let solution: Solution = read_input()?;
+    if lint {
+        print!("{}", solution.report());
+    } else {
+        for repair in solution.repairs() {
+            println!("{}", repair.line);
+        }
+    }
+
+    Ok(())
 }
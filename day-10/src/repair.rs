@@ -0,0 +1,249 @@
+use crate::config::{Config, UnknownCharacters};
+
+/// Single change of a line, offsets are in bytes of the original line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// insert the character before `offset`, the end of the line if it's equal to its length
+    Insert {
+        offset: usize,
+        c: char,
+    },
+    Delete {
+        offset: usize,
+        c: char,
+    },
+    Substitute {
+        offset: usize,
+        found: char,
+        replacement: char,
+    },
+}
+
+impl Edit {
+    pub fn offset(&self) -> usize {
+        match *self {
+            Self::Insert { offset, .. }
+            | Self::Delete { offset, .. }
+            | Self::Substitute { offset, .. } => offset,
+        }
+    }
+}
+
+/// Balanced version of a line, made with the fewest edits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub line: String,
+    /// edits in order of their offsets
+    pub edits: Vec<Edit>,
+}
+
+impl Repair {
+    /// Number of edits
+    pub fn cost(&self) -> usize {
+        self.edits.len()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    offset: usize,
+    c: char,
+    pair: usize,
+    side: Side,
+}
+
+/// Cheapest way to turn a pair of brackets into a matching pair
+#[derive(Debug, Clone, Copy)]
+struct Match {
+    cost: u32,
+    open: char,
+    close: char,
+}
+
+/// Make the line balanced with the minimum number of insertions, deletions and substitutions.
+///
+/// Among repairs of the same cost, matching brackets are preferred, then closing unclosed
+/// brackets and deleting stray closing ones. Characters, which aren't brackets, are kept if
+/// they are ignored by the config and deleted otherwise. Takes O(n³) time for n brackets.
+pub fn repair(line: &str, config: &Config) -> Repair {
+    let mut tokens = vec![];
+    let mut unknown = vec![];
+
+    for (offset, c) in line.char_indices() {
+        let (pair, side) = if let Some(pair) = config.opened_by(c) {
+            (pair, Side::Open)
+        } else if let Some(pair) = config.closed_by(c) {
+            (pair, Side::Close)
+        } else {
+            if config.unknown_characters() == UnknownCharacters::Error {
+                unknown.push(Edit::Delete { offset, c });
+            }
+            continue;
+        };
+
+        let pair = config
+            .pairs()
+            .iter()
+            .position(|candidate| candidate == pair)
+            .unwrap();
+        tokens.push(Token {
+            offset,
+            c,
+            pair,
+            side,
+        });
+    }
+
+    let table = CostTable::new(&tokens, config);
+    let mut edits = Vec::with_capacity(table.cost(0, tokens.len()) as usize + unknown.len());
+    table.edits(0, tokens.len(), line.len(), &mut edits);
+
+    // both lists are ordered and never share an offset
+    edits.extend(unknown);
+    edits.sort_by_key(Edit::offset);
+
+    Repair {
+        line: apply(line, &edits),
+        edits,
+    }
+}
+
+/// Minimum cost of balancing every range of tokens
+struct CostTable<'a> {
+    tokens: &'a [Token],
+    config: &'a Config,
+    /// cost of `tokens[i..j]` at `i * (n + 1) + j`
+    costs: Vec<u32>,
+}
+
+impl<'a> CostTable<'a> {
+    fn new(tokens: &'a [Token], config: &'a Config) -> Self {
+        let n = tokens.len();
+        let mut table = Self {
+            tokens,
+            config,
+            costs: vec![0; (n + 1) * (n + 1)],
+        };
+
+        for len in 1..=n {
+            for i in 0..=n - len {
+                let j = i + len;
+                let cost = table.best_split(i, j).map_or(u32::MAX, |(_, cost)| cost);
+                table.costs[i * (n + 1) + j] = cost.min(1 + table.cost(i + 1, j));
+            }
+        }
+
+        table
+    }
+
+    fn cost(&self, i: usize, j: usize) -> u32 {
+        self.costs[i * (self.tokens.len() + 1) + j]
+    }
+
+    /// Cheapest way to change two tokens into a pair, the opening one first
+    fn matching(&self, first: Token, second: Token) -> Match {
+        let pairs = self.config.pairs();
+        let (open, close) = match (first.side, second.side) {
+            (Side::Open, Side::Close) if first.pair == second.pair => (first.pair, second.pair),
+            (Side::Open, _) => (first.pair, first.pair),
+            (_, Side::Close) => (second.pair, second.pair),
+            (Side::Close, Side::Open) => (first.pair, first.pair),
+        };
+        let (open, close) = (pairs[open].open, pairs[close].close);
+
+        Match {
+            cost: u32::from(first.c != open) + u32::from(second.c != close),
+            open,
+            close,
+        }
+    }
+
+    /// The best token to pair with `tokens[i]` within `tokens[i..j]` with the total cost
+    fn best_split(&self, i: usize, j: usize) -> Option<(usize, u32)> {
+        (i + 1..j)
+            .map(|k| {
+                let matching = self.matching(self.tokens[i], self.tokens[k]);
+                (k, matching.cost + self.cost(i + 1, k) + self.cost(k + 1, j))
+            })
+            .min_by_key(|&(_, cost)| cost)
+    }
+
+    /// Edits of the cheapest repair of `tokens[i..j]`, which is followed by the byte offset `end`
+    fn edits(&self, i: usize, j: usize, end: usize, edits: &mut Vec<Edit>) {
+        if i == j {
+            return;
+        }
+
+        let token = self.tokens[i];
+
+        match self.best_split(i, j) {
+            Some((k, cost)) if cost == self.cost(i, j) => {
+                let matching = self.matching(token, self.tokens[k]);
+                substitute(token, matching.open, edits);
+                self.edits(i + 1, k, self.tokens[k].offset, edits);
+                substitute(self.tokens[k], matching.close, edits);
+                self.edits(k + 1, j, end, edits);
+            }
+            // an unclosed bracket is closed at the end of the range, a stray one is deleted
+            _ => match token.side {
+                Side::Open => {
+                    self.edits(i + 1, j, end, edits);
+                    edits.push(Edit::Insert {
+                        offset: end,
+                        c: self.config.pairs()[token.pair].close,
+                    });
+                }
+                Side::Close => {
+                    edits.push(Edit::Delete {
+                        offset: token.offset,
+                        c: token.c,
+                    });
+                    self.edits(i + 1, j, end, edits);
+                }
+            },
+        }
+    }
+}
+
+fn substitute(token: Token, replacement: char, edits: &mut Vec<Edit>) {
+    if token.c != replacement {
+        edits.push(Edit::Substitute {
+            offset: token.offset,
+            found: token.c,
+            replacement,
+        });
+    }
+}
+
+/// Line with all edits, ordered by offsets, applied
+fn apply(line: &str, edits: &[Edit]) -> String {
+    let mut repaired = String::with_capacity(line.len() + edits.len());
+    let mut edits = edits.iter().peekable();
+
+    for (offset, c) in line.char_indices().chain([(line.len(), '\0')]) {
+        let mut keep = offset < line.len();
+
+        while let Some(edit) = edits.next_if(|edit| edit.offset() == offset) {
+            match *edit {
+                Edit::Insert { c, .. } => repaired.push(c),
+                Edit::Delete { .. } => keep = false,
+                Edit::Substitute { replacement, .. } => {
+                    repaired.push(replacement);
+                    keep = false;
+                }
+            }
+        }
+
+        if keep {
+            repaired.push(c);
+        }
+    }
+
+    repaired
+}
@@ -0,0 +1,171 @@
+use bitvec::prelude::*;
+
+use crate::grid::Grid;
+
+/// How cells change during a step
+pub trait Rule {
+    /// energy, gained by every cell at the start of a step
+    fn increment(&self, cell: u8) -> u8;
+    /// whether the cell fires, it happens at most once per step
+    fn fires(&self, cell: u8) -> bool;
+    /// energy, gained by a cell from a firing neighbour
+    fn cascade(&self, cell: u8) -> u8;
+    /// value of a fired cell at the end of the step
+    fn reset(&self, cell: u8) -> u8;
+}
+
+/// Rules of the puzzle: octopuses flash above 9 and their neighbours gain energy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Octopuses;
+
+impl Rule for Octopuses {
+    fn increment(&self, cell: u8) -> u8 {
+        cell.saturating_add(1)
+    }
+
+    fn fires(&self, cell: u8) -> bool {
+        cell > 9
+    }
+
+    fn cascade(&self, cell: u8) -> u8 {
+        cell.saturating_add(1)
+    }
+
+    fn reset(&self, _cell: u8) -> u8 {
+        0
+    }
+}
+
+/// What lies beyond the edges of the grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    /// nothing, edge cells have fewer neighbours
+    Bounded,
+    /// the opposite side for left and right edges, nothing for top and bottom ones
+    Cylinder,
+    /// the opposite side for all edges
+    Torus,
+}
+
+const OFFSETS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// Grid of cells, changing step by step according to the rule
+#[derive(Debug, Clone)]
+pub struct Automaton<R> {
+    rule: R,
+    grid: Grid,
+    /// neighbours of cell `i` are `neighbours[starts[i]..starts[i + 1]]`
+    starts: Vec<u32>,
+    neighbours: Vec<u32>,
+    /// cells, fired during the last step
+    fired: BitVec,
+    queue: Vec<u32>,
+}
+
+impl<R: Rule> Automaton<R> {
+    pub fn new(grid: Grid, rule: R, boundary: Boundary) -> Self {
+        let (width, height) = (grid.width() as isize, grid.height() as isize);
+        let n_cells = grid.cells().len();
+
+        let mut starts = Vec::with_capacity(n_cells + 1);
+        let mut neighbours = Vec::with_capacity(n_cells * OFFSETS.len());
+        for y in 0..height {
+            for x in 0..width {
+                starts.push(neighbours.len() as u32);
+                let first = neighbours.len();
+
+                for (dx, dy) in OFFSETS {
+                    let (mut nx, mut ny) = (x + dx, y + dy);
+                    if boundary != Boundary::Bounded {
+                        nx = nx.rem_euclid(width);
+                    }
+                    if boundary == Boundary::Torus {
+                        ny = ny.rem_euclid(height);
+                    }
+                    if !(0..width).contains(&nx) || !(0..height).contains(&ny) {
+                        continue;
+                    }
+
+                    // on narrow grids several offsets wrap to the same cell or the cell itself
+                    let neighbour = (ny * width + nx) as u32;
+                    if (nx, ny) != (x, y) && !neighbours[first..].contains(&neighbour) {
+                        neighbours.push(neighbour);
+                    }
+                }
+            }
+        }
+        starts.push(neighbours.len() as u32);
+
+        Self {
+            rule,
+            grid,
+            starts,
+            neighbours,
+            fired: bitvec![0; n_cells],
+            queue: vec![],
+        }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    pub fn rule(&self) -> &R {
+        &self.rule
+    }
+
+    /// Cells, fired during the last step, indexed as `y * width + x`
+    pub fn fired(&self) -> &BitSlice {
+        &self.fired
+    }
+
+    /// Make a step, returning the number of fired cells
+    pub fn step(&mut self) -> usize {
+        let Self {
+            rule,
+            grid,
+            starts,
+            neighbours,
+            fired,
+            queue,
+        } = self;
+        let cells = grid.cells_mut();
+
+        fired.fill(false);
+        for (i, cell) in cells.iter_mut().enumerate() {
+            *cell = rule.increment(*cell);
+            if rule.fires(*cell) {
+                fired.set(i, true);
+                queue.push(i as u32);
+            }
+        }
+
+        while let Some(i) = queue.pop() {
+            let i = i as usize;
+            for &neighbour in &neighbours[starts[i] as usize..starts[i + 1] as usize] {
+                let neighbour = neighbour as usize;
+                cells[neighbour] = rule.cascade(cells[neighbour]);
+
+                if !fired[neighbour] && rule.fires(cells[neighbour]) {
+                    fired.set(neighbour, true);
+                    queue.push(neighbour as u32);
+                }
+            }
+        }
+
+        for i in fired.iter_ones() {
+            cells[i] = rule.reset(cells[i]);
+        }
+
+        fired.count_ones()
+    }
+}
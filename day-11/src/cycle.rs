@@ -0,0 +1,112 @@
+use displaydoc::Display;
+use rustc_hash::FxHashMap;
+use thiserror::Error;
+
+use crate::automaton::{Automaton, Rule};
+
+/// Repeating part of the automaton history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// the first step, after which the state is repeated later, 0 for the initial state
+    pub start: usize,
+    /// number of steps between repeated states
+    pub period: usize,
+}
+
+/// Everything, that happens to an automaton, up to the end of its first cycle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trajectory {
+    n_cells: usize,
+    /// number of fired cells at each step, starting with the first one
+    fired: Vec<usize>,
+    cycle: Cycle,
+}
+
+/// Cycle search error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum CycleError {
+    /// no state repeated within {0} steps
+    StepLimit(usize),
+}
+
+impl Trajectory {
+    /// Step the automaton until its state repeats, remembering all states seen so far
+    pub fn new<R: Rule>(
+        automaton: &mut Automaton<R>,
+        max_steps: usize,
+    ) -> Result<Self, CycleError> {
+        let mut seen = FxHashMap::default();
+        let mut fired = vec![];
+
+        seen.insert(automaton.grid().cells().to_vec(), 0);
+        for step in 1..=max_steps {
+            fired.push(automaton.step());
+
+            let state = automaton.grid().cells();
+            if let Some(&start) = seen.get(state) {
+                return Ok(Self {
+                    n_cells: state.len(),
+                    fired,
+                    cycle: Cycle {
+                        start,
+                        period: step - start,
+                    },
+                });
+            }
+            seen.insert(state.to_vec(), step);
+        }
+
+        Err(CycleError::StepLimit(max_steps))
+    }
+
+    pub fn cycle(&self) -> Cycle {
+        self.cycle
+    }
+
+    /// Number of cells, fired at the step, which starts from 1, `None` for the initial state
+    pub fn fired_at(&self, step: usize) -> Option<usize> {
+        if step == 0 {
+            return None;
+        }
+
+        let step = if step > self.fired.len() {
+            let cycle = self.cycle;
+            cycle.start + (step - cycle.start - 1) % cycle.period + 1
+        } else {
+            step
+        };
+
+        Some(self.fired[step - 1])
+    }
+
+    /// Total number of cells, fired during the first `steps` steps
+    pub fn total_fired(&self, steps: usize) -> u64 {
+        let prefix = steps.min(self.fired.len());
+        let mut total = self.fired[..prefix].iter().map(|&n| n as u64).sum::<u64>();
+
+        if steps > prefix {
+            let cycle = &self.fired[self.cycle.start..];
+            let per_cycle = cycle.iter().map(|&n| n as u64).sum::<u64>();
+            let (full, rest) = (
+                (steps - prefix) / self.cycle.period,
+                (steps - prefix) % self.cycle.period,
+            );
+            total += full as u64 * per_cycle + cycle[..rest].iter().map(|&n| n as u64).sum::<u64>();
+        }
+
+        total
+    }
+
+    /// The first step, when all cells fire at once, `None` if it never happens
+    pub fn synchronization(&self) -> Option<usize> {
+        self.fired
+            .iter()
+            .position(|&n| n == self.n_cells)
+            .map(|i| i + 1)
+    }
+
+    /// Whether all cells keep firing at once periodically, once the cycle is reached
+    pub fn synchronizes_periodically(&self) -> bool {
+        self.fired[self.cycle.start..].contains(&self.n_cells)
+    }
+}
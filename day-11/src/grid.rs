@@ -0,0 +1,110 @@
+use std::{fmt, str::FromStr};
+
+use displaydoc::Display;
+use thiserror::Error;
+
+/// Rectangular grid of cells, stored row by row
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+/// Grid parsing error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ParseGridError {
+    /// grid has no cells
+    Empty,
+    /// row {row} has {len} cells, while the first one has {width}
+    RaggedRow {
+        row: usize,
+        len: usize,
+        width: usize,
+    },
+    /// invalid digit {found:?} at row {row}, column {col}
+    InvalidDigit { row: usize, col: usize, found: char },
+}
+
+impl FromStr for Grid {
+    type Err = ParseGridError;
+
+    /// Rows of single digit cells
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = vec![];
+        let mut width = 0;
+
+        let lines = s.lines().map(str::trim).filter(|line| !line.is_empty());
+        for (row, line) in lines.enumerate() {
+            let row_start = cells.len();
+            for (col, c) in line.chars().enumerate() {
+                let digit =
+                    c.to_digit(10)
+                        .ok_or(ParseGridError::InvalidDigit { row, col, found: c })?;
+                cells.push(digit as u8);
+            }
+
+            let len = cells.len() - row_start;
+            if row == 0 {
+                width = len;
+            } else if len != width {
+                return Err(ParseGridError::RaggedRow { row, len, width });
+            }
+        }
+
+        if cells.is_empty() {
+            return Err(ParseGridError::Empty);
+        }
+
+        Ok(Self {
+            width,
+            height: cells.len() / width,
+            cells,
+        })
+    }
+}
+
+impl Grid {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u8 {
+        self.cells[y * self.width + x]
+    }
+
+    /// All cells, row by row
+    pub fn cells(&self) -> &[u8] {
+        &self.cells
+    }
+
+    pub(crate) fn cells_mut(&mut self) -> &mut [u8] {
+        &mut self.cells
+    }
+
+    /// Rows of cells from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[u8]> {
+        self.cells.chunks(self.width)
+    }
+}
+
+impl fmt::Display for Grid {
+    /// Rows of digits, cells above 9 are shown as `+`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.rows() {
+            for &cell in row {
+                match cell {
+                    0..=9 => write!(f, "{}", cell)?,
+                    _ => write!(f, "+")?,
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
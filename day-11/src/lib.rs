@@ -1,127 +1,78 @@
-use std::{collections::BTreeSet, error::Error, iter::once, str::FromStr};
+use std::{error::Error, str::FromStr};
 
 use advent_utils::{Part, Solver};
 
+pub use automaton::{Automaton, Boundary, Octopuses, Rule};
+pub use cycle::{Cycle, CycleError, Trajectory};
+pub use grid::{Grid, ParseGridError};
+pub use recorder::{Frame, Recorder};
+
+mod automaton;
+mod cycle;
+mod grid;
+mod recorder;
+
 #[derive(Debug)]
 pub struct Solution {
-    octopuses: Vec<Vec<u8>>,
+    grid: Grid,
+    boundary: Boundary,
 }
 
-const N_STEPS: usize = 100;
+pub const N_STEPS: usize = 100;
+/// States of the puzzle grid are stored until one repeats, so this also bounds memory usage
+const MAX_STEPS: usize = 100_000;
 
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut octopuses: Vec<Vec<u8>> = s
-            .trim_end()
-            .split('\n')
-            .map(|line| {
-                once(u8::MAX)
-                    .chain(line.bytes().map(|b| b - b'0'))
-                    .chain(once(u8::MAX))
-                    .collect()
-            })
-            .collect();
-
-        let line_size = octopuses[0].len();
-
-        octopuses.insert(0, vec![u8::MAX; line_size]);
-        octopuses.push(vec![u8::MAX; line_size]);
-
-        Ok(Self { octopuses })
+        Ok(Self {
+            grid: s.parse()?,
+            boundary: Boundary::Bounded,
+        })
     }
 }
 
-fn step(octopuses: &mut [Vec<u8>]) -> usize {
-    let mut process_flashed = vec![];
-    let mut flashed = BTreeSet::new();
-
-    for y in 1..octopuses.len() - 1 {
-        for x in 1..octopuses[y].len() - 1 {
-            octopuses[y][x] += 1;
-
-            if octopuses[y][x] > 9 {
-                flashed.insert((x, y));
-
-                process_flashed.push((x, y));
-            }
-        }
+impl Solution {
+    /// Replace puzzle edges with custom ones
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self { boundary, ..self }
     }
 
-    while let Some((x, y)) = process_flashed.pop() {
-        for (x_n, y_n) in [
-            (x - 1, y - 1),
-            (x, y - 1),
-            (x + 1, y - 1),
-            (x - 1, y),
-            (x + 1, y),
-            (x - 1, y + 1),
-            (x, y + 1),
-            (x + 1, y + 1),
-        ] {
-            if octopuses[y_n][x_n] == u8::MAX {
-                continue;
-            }
-
-            octopuses[y_n][x_n] += 1;
-
-            if octopuses[y_n][x_n] > 9 && flashed.insert((x_n, y_n)) {
-                process_flashed.push((x_n, y_n));
-            }
-        }
+    pub fn grid(&self) -> &Grid {
+        &self.grid
     }
 
-    for &(x, y) in flashed.iter() {
-        octopuses[y][x] = 0;
+    /// History of octopuses up to the first repeated state
+    pub fn trajectory(&self, max_steps: usize) -> Result<Trajectory, CycleError> {
+        Trajectory::new(&mut self.automaton(), max_steps)
     }
 
-    #[cfg(feature = "preview")]
-    {
-        print!("{}{}", termion::clear::All, termion::cursor::Goto(1, 1));
-
-        for y in 1..octopuses.len() - 1 {
-            for x in 1..octopuses[y].len() - 1 {
-                if flashed.contains(&(x, y)) {
-                    print!(
-                        "{}{}{}",
-                        termion::style::Bold,
-                        octopuses[y][x],
-                        termion::style::Reset,
-                    )
-                } else {
-                    print!("{}", octopuses[y][x])
-                };
-            }
-            println!();
-        }
-
-        std::thread::sleep(std::time::Duration::from_millis(250));
+    /// Octopuses in their initial state
+    pub fn automaton(&self) -> Automaton<Octopuses> {
+        Automaton::new(self.grid.clone(), Octopuses, self.boundary)
     }
-
-    flashed.len()
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
-        let mut octopuses = self.octopuses.clone();
+        let mut octopuses = self.automaton();
 
         match part {
             Part::One => {
-                let total_flashes = (0..N_STEPS).map(|_| step(&mut octopuses)).sum::<usize>();
+                let total_flashes = (0..N_STEPS).map(|_| octopuses.step()).sum::<usize>();
 
                 format!("there were total {} flashes", total_flashes)
             }
-            Part::Two => {
-                let total_octopuses = (octopuses.len() - 2) * (octopuses[0].len() - 2);
-
-                match (1..).find(|_| step(&mut octopuses) == total_octopuses) {
+            Part::Two => match self.trajectory(MAX_STEPS) {
+                Ok(trajectory) => match trajectory.synchronization() {
                     Some(step_no) => {
                         format!("all octopuses will be synchronized after {} steps", step_no)
                     }
                     None => "octopuses failed to synchronize :(".to_owned(),
-                }
-            }
+                },
+                Err(e) => format!("octopuses failed to synchronize: {}", e),
+            },
         }
     }
 
@@ -132,9 +83,33 @@ impl Solver for Solution {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use super::*;
     use indoc::indoc;
 
+    /// Cells fire at 3 and don't spread energy, so they just count steps modulo 4
+    /// and never catch up with each other
+    struct Counter;
+
+    impl Rule for Counter {
+        fn increment(&self, cell: u8) -> u8 {
+            cell + 1
+        }
+
+        fn fires(&self, cell: u8) -> bool {
+            cell > 3
+        }
+
+        fn cascade(&self, cell: u8) -> u8 {
+            cell
+        }
+
+        fn reset(&self, _cell: u8) -> u8 {
+            0
+        }
+    }
+
     #[test]
     fn test_small_example() {
         let solution: Solution = indoc!(
@@ -148,10 +123,22 @@ mod tests {
         .parse()
         .unwrap();
 
-        let mut octopuses = solution.octopuses;
+        let mut octopuses = solution.automaton();
 
-        assert_eq!(step(&mut octopuses), 9);
-        assert_eq!(step(&mut octopuses), 0);
+        assert_eq!(octopuses.step(), 9);
+        assert_eq!(octopuses.step(), 0);
+        assert_eq!(
+            octopuses.grid().to_string(),
+            indoc!(
+                "
+                45654
+                51115
+                61116
+                51115
+                45654
+                "
+            )
+        );
     }
 
     #[test]
@@ -179,4 +166,193 @@ mod tests {
             "all octopuses will be synchronized after 195 steps"
         );
     }
+
+    #[test]
+    fn test_boundaries() {
+        let grid = indoc!(
+            "
+            91111
+            11111
+            11111
+            11111"
+        );
+
+        let mut bounded = grid.parse::<Solution>().unwrap().automaton();
+        assert_eq!(bounded.step(), 1);
+        assert_eq!(bounded.fired().iter_ones().collect::<Vec<_>>(), [0]);
+        assert_eq!(
+            bounded.grid().to_string(),
+            indoc!(
+                "
+                03222
+                33222
+                22222
+                22222
+                "
+            )
+        );
+
+        // the corner touches the opposite edges and corners
+        let mut torus = grid
+            .parse::<Solution>()
+            .unwrap()
+            .with_boundary(Boundary::Torus)
+            .automaton();
+        assert_eq!(torus.step(), 1);
+        assert_eq!(
+            torus.grid().to_string(),
+            indoc!(
+                "
+                03223
+                33223
+                22222
+                33223
+                "
+            )
+        );
+
+        let mut cylinder = grid
+            .parse::<Solution>()
+            .unwrap()
+            .with_boundary(Boundary::Cylinder)
+            .automaton();
+        cylinder.step();
+        assert_eq!(
+            cylinder.grid().to_string(),
+            indoc!(
+                "
+                03223
+                33223
+                22222
+                22222
+                "
+            )
+        );
+
+        // every cell of a 2×2 torus neighbours every other one exactly once
+        let mut small = "99\n88"
+            .parse::<Solution>()
+            .unwrap()
+            .with_boundary(Boundary::Torus)
+            .automaton();
+        assert_eq!(small.step(), 4);
+    }
+
+    #[test]
+    fn test_custom_rule() {
+        let mut counter = Automaton::new("0123".parse().unwrap(), Counter, Boundary::Torus);
+        assert_eq!(counter.step(), 1);
+        assert_eq!(counter.grid().cells(), [1, 2, 3, 0]);
+
+        assert!(matches!(
+            "12\n3".parse::<Grid>(),
+            Err(ParseGridError::RaggedRow { row: 1, .. })
+        ));
+        assert!("1a".parse::<Solution>().is_err());
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let solution: Solution = indoc!(
+            "
+            5483143223
+            2745854711
+            5264556173
+            6141336146
+            6357385478
+            4167524645
+            2176841721
+            6882881134
+            4846848554
+            5283751526"
+        )
+        .parse()
+        .unwrap();
+        let trajectory = solution.trajectory(1000).unwrap();
+
+        // once synchronized, octopuses flash together every 10 steps
+        assert_eq!(trajectory.synchronization(), Some(195));
+        assert!(trajectory.synchronizes_periodically());
+        assert_eq!(trajectory.cycle().period, 10);
+        assert!(trajectory.cycle().start <= 195);
+        assert_eq!(trajectory.fired_at(205), Some(100));
+        assert_eq!(trajectory.fired_at(10_195), Some(100));
+        assert_eq!(trajectory.fired_at(0), None);
+        assert_eq!(trajectory.total_fired(100), 1656);
+
+        let mut octopuses = solution.automaton();
+        let total = (0..1000).map(|_| octopuses.step() as u64).sum::<u64>();
+        assert_eq!(trajectory.total_fired(1000), total);
+
+        assert_eq!(solution.trajectory(50), Err(CycleError::StepLimit(50)));
+    }
+
+    #[test]
+    fn test_never_synchronized() {
+        let mut counter = Automaton::new("0123".parse().unwrap(), Counter, Boundary::Bounded);
+        let trajectory = Trajectory::new(&mut counter, 100).unwrap();
+
+        assert_eq!(
+            trajectory.cycle(),
+            Cycle {
+                start: 0,
+                period: 4
+            }
+        );
+        assert_eq!(trajectory.synchronization(), None);
+        assert!(!trajectory.synchronizes_periodically());
+        assert_eq!(trajectory.total_fired(10), 10);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let solution: Solution = indoc!(
+            "
+            11111
+            19991
+            19191
+            19991
+            11111"
+        )
+        .parse()
+        .unwrap();
+        let recorder = Recorder::run(&mut solution.automaton(), 2);
+
+        assert_eq!(recorder.frames().len(), 3);
+        assert_eq!(recorder.frames()[0].grid, *solution.grid());
+        assert_eq!(recorder.frames()[1].fired.count_ones(), 9);
+        assert!(recorder.frames()[1].fired[6]);
+        assert!(!recorder.frames()[1].fired[0]);
+
+        assert!(recorder.to_text().starts_with(indoc!(
+            "
+            step 0: 0 fired
+            11111
+            19991
+            19191
+            19991
+            11111
+
+            step 1: 9 fired
+            34543
+            40004
+            50005
+            40004
+            34543
+            "
+        )));
+
+        let cast = recorder.to_asciicast(Duration::from_millis(250));
+        let lines = cast.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], r#"{"version": 2, "width": 5, "height": 5}"#);
+        assert!(
+            lines[2].starts_with(r#"[0.250, "o", "\u001b[2J\u001b[H34543\r\n4\u001b[1m0\u001b[0m"#)
+        );
+
+        let pgm = recorder.to_pgm();
+        assert_eq!(pgm.len(), 3);
+        assert!(pgm[1].starts_with(b"P5\n5 5\n10\n"));
+        assert_eq!(&pgm[1][pgm[1].len() - 25..][..7], [3, 4, 5, 4, 3, 4, 10]);
+    }
 }
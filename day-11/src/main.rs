@@ -1,9 +1,62 @@
-use std::error::Error;
+use std::{env::var, error::Error, fs, path::Path, time::Duration};
 
+use advent_input::read_input;
 use advent_utils::Solver;
 
-use day_11::Solution;
+use day_11::{Recorder, Solution, N_STEPS};
+
+/// Time between frames at normal speed
+const FRAME_DURATION: Duration = Duration::from_millis(250);
 
 fn main() -> Result<(), Box<dyn Error>> {
-    Solution::solve_env_config()
+    // `RECORD=asciicast|text|pgm` records `STEPS` steps instead of solving,
+    // `SPEED` makes recordings and the preview faster or slower
+    let record = var("RECORD").ok();
+    if record.is_none() && cfg!(not(feature = "preview")) {
+        return Solution::solve_env_config();
+    }
+
+    let solution: Solution = read_input()?;
+
+    let steps = match var("STEPS") {
+        Ok(steps) => steps.parse()?,
+        Err(_) => N_STEPS,
+    };
+    let speed: f64 = match var("SPEED") {
+        Ok(speed) => speed.parse()?,
+        Err(_) => 1.0,
+    };
+    if !(speed.is_finite() && speed > 0.0) {
+        return Err(format!("speed must be a positive finite number, got {}", speed).into());
+    }
+    // very slow speeds make the duration too long for `Duration`
+    let frame_duration = Duration::try_from_secs_f64(FRAME_DURATION.as_secs_f64() / speed)?;
+
+    let recorder = Recorder::run(&mut solution.automaton(), steps);
+
+    match record.as_deref() {
+        Some("asciicast") => print!("{}", recorder.to_asciicast(frame_duration)),
+        Some("text") => print!("{}", recorder.to_text()),
+        Some("pgm") => {
+            // frames are written to `OUTPUT` directory, `frames` by default
+            let output = var("OUTPUT").unwrap_or_else(|_| "frames".to_owned());
+            fs::create_dir_all(&output)?;
+
+            for (step, pgm) in recorder.to_pgm().iter().enumerate() {
+                fs::write(Path::new(&output).join(format!("{:04}.pgm", step)), pgm)?;
+            }
+        }
+        Some(format) => return Err(format!("unknown recording format {:?}", format).into()),
+        None => {
+            #[cfg(feature = "preview")]
+            recorder.play(frame_duration);
+
+            // solve the same input, which was played
+            for part in Solution::implemented_parts() {
+                println!("{}", solution.solve(part));
+            }
+        }
+    }
+
+    Ok(())
 }
@@ -0,0 +1,187 @@
+use std::{fmt::Write, time::Duration};
+
+use bitvec::prelude::*;
+
+use crate::{
+    automaton::{Automaton, Rule},
+    grid::Grid,
+};
+
+/// Brightness of fired cells in PGM frames, other cells are as bright as their values
+const PGM_FIRED: u8 = 10;
+
+/// State of an automaton after a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// 0 for the initial state
+    pub step: usize,
+    pub grid: Grid,
+    /// cells, fired during the step, indexed as `y * width + x`
+    pub fired: BitVec,
+}
+
+impl Frame {
+    /// Rows of digits with fired cells in bold, each line ending with `\r\n` as terminals expect
+    pub fn to_ansi(&self) -> String {
+        let mut ansi = String::new();
+
+        for (y, row) in self.grid.rows().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                let cell = char::from_digit(u32::from(cell), 10).unwrap_or('+');
+                if self.fired[y * self.grid.width() + x] {
+                    write!(ansi, "\x1b[1m{}\x1b[0m", cell).expect("writing to string never fails");
+                } else {
+                    ansi.push(cell);
+                }
+            }
+            ansi.push_str("\r\n");
+        }
+
+        ansi
+    }
+
+    /// Binary greymap with fired cells the brightest
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let (width, height) = (self.grid.width(), self.grid.height());
+        let mut pgm = format!("P5\n{} {}\n{}\n", width, height, PGM_FIRED).into_bytes();
+
+        pgm.extend(
+            self.grid
+                .cells()
+                .iter()
+                .zip(&self.fired)
+                .map(|(&cell, fired)| {
+                    if *fired {
+                        PGM_FIRED
+                    } else {
+                        cell.min(PGM_FIRED - 1)
+                    }
+                }),
+        );
+
+        pgm
+    }
+}
+
+/// Frames of every step of an automaton
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recorder {
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the initial state and `steps` steps of the automaton
+    pub fn run<R: Rule>(automaton: &mut Automaton<R>, steps: usize) -> Self {
+        let mut recorder = Self::new();
+
+        recorder.record(
+            0,
+            automaton.grid(),
+            &bitvec![0; automaton.grid().cells().len()],
+        );
+        for step in 1..=steps {
+            automaton.step();
+            recorder.record(step, automaton.grid(), automaton.fired());
+        }
+
+        recorder
+    }
+
+    pub fn record(&mut self, step: usize, grid: &Grid, fired: &BitSlice) {
+        self.frames.push(Frame {
+            step,
+            grid: grid.clone(),
+            fired: fired.to_bitvec(),
+        });
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// All frames with headers, separated by empty lines
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            if i > 0 {
+                text.push('\n');
+            }
+            writeln!(
+                text,
+                "step {}: {} fired",
+                frame.step,
+                frame.fired.count_ones()
+            )
+            .expect("writing to string never fails");
+            write!(text, "{}", frame.grid).expect("writing to string never fails");
+        }
+
+        text
+    }
+
+    /// Recording in asciicast v2 format, showing a frame each `frame_duration`
+    pub fn to_asciicast(&self, frame_duration: Duration) -> String {
+        let (width, height) = self
+            .frames
+            .first()
+            .map_or((0, 0), |frame| (frame.grid.width(), frame.grid.height()));
+        let mut cast = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}\n",
+            width, height
+        );
+
+        for (i, frame) in self.frames.iter().enumerate() {
+            let time = frame_duration.as_secs_f64() * i as f64;
+            let output = format!("\x1b[2J\x1b[H{}", frame.to_ansi());
+
+            writeln!(cast, "[{:.3}, \"o\", \"{}\"]", time, json_escape(&output))
+                .expect("writing to string never fails");
+        }
+
+        cast
+    }
+
+    /// Binary greymap of every frame
+    pub fn to_pgm(&self) -> Vec<Vec<u8>> {
+        self.frames.iter().map(Frame::to_pgm).collect()
+    }
+
+    /// Show frames in the terminal, one each `frame_duration`
+    #[cfg(feature = "preview")]
+    pub fn play(&self, frame_duration: Duration) {
+        for frame in &self.frames {
+            print!(
+                "{}{}{}",
+                termion::clear::All,
+                termion::cursor::Goto(1, 1),
+                frame.to_ansi()
+            );
+            std::thread::sleep(frame_duration);
+        }
+    }
+}
+
+/// Contents of a JSON string literal
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c if c.is_control() => {
+                write!(escaped, "\\u{:04x}", c as u32).expect("writing to string never fails")
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -1,8 +1,10 @@
-use std::{collections::BTreeMap, error::Error, str::FromStr};
+use std::{error::Error, str::FromStr};
 
 use advent_utils::{Part, Solver};
-use smallvec::SmallVec;
+use displaydoc::Display;
+use rustc_hash::FxHashMap;
 use string_interner::{DefaultSymbol, StringInterner};
+use thiserror::Error;
 
 #[derive(Debug)]
 pub struct Solution {
@@ -10,112 +12,172 @@ pub struct Solution {
     /// being dwarfed by allocation time, so I use string interning here to get rid
     /// of most of allocations (reduces runtime ~threefold)
     interner: StringInterner,
-    /// Map, containing the caves, accessible from the given cave
-    caves_map: BTreeMap<Cave, SmallVec<[Cave; 32]>>,
+    /// Index of each cave in the order of appearance
+    indices: FxHashMap<DefaultSymbol, u32>,
+    /// Caves, accessible from the given cave
+    neighbours: Vec<Vec<u32>>,
+    /// Bit of the given small cave in masks of visited caves, `None` for big caves
+    small_bits: Vec<Option<u32>>,
 }
 
 const START: &str = "start";
 const END: &str = "end";
 
+/// Visited small caves are tracked in bits of `u64`
+const MAX_SMALL_CAVES: usize = u64::BITS as usize;
+
+/// Cave system parsing error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum ParseCavesError {
+    /// there are {0} small caves, while at most 64 are supported
+    TooManySmallCaves(usize),
+    /// big caves {0} and {1} are connected, so there are infinitely many paths
+    AdjacentBigCaves(String, String),
+}
+
+/// Path counting error
+#[derive(Debug, Display, Error, PartialEq, Eq)]
+pub enum PathsError {
+    /// there is no cave {0}
+    UnknownCave(String),
+    /// number of paths doesn't fit into u128
+    Overflow,
+}
+
 impl FromStr for Solution {
     type Err = Box<dyn Error>;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut interner = StringInterner::new();
-        let mut cave_map: BTreeMap<Cave, SmallVec<[Cave; 32]>> = BTreeMap::new();
+        let mut interner: StringInterner = StringInterner::new();
+        let mut indices = FxHashMap::default();
+        let mut neighbours: Vec<Vec<u32>> = vec![];
+        let mut small_bits = vec![];
+        let mut n_small = 0;
 
         for line in s.lines() {
             if let Some((from, to)) = line.split_once('-') {
-                let from_cave = Cave::from_str(from, &mut interner);
-                let to_cave = Cave::from_str(to, &mut interner);
+                if !is_small(from) && !is_small(to) {
+                    return Err(
+                        ParseCavesError::AdjacentBigCaves(from.to_owned(), to.to_owned()).into(),
+                    );
+                }
 
-                cave_map.entry(from_cave).or_default().push(to_cave);
-                cave_map.entry(to_cave).or_default().push(from_cave);
+                let [from_cave, to_cave] = [from, to].map(|name| {
+                    let symbol = interner.get_or_intern(name);
+                    *indices.entry(symbol).or_insert_with(|| {
+                        neighbours.push(vec![]);
+                        small_bits.push(is_small(name).then(|| {
+                            n_small += 1;
+                            n_small as u32 - 1
+                        }));
+
+                        neighbours.len() as u32 - 1
+                    }) as usize
+                });
+
+                neighbours[from_cave].push(to_cave as u32);
+                neighbours[to_cave].push(from_cave as u32);
             }
         }
 
+        if n_small > MAX_SMALL_CAVES {
+            return Err(ParseCavesError::TooManySmallCaves(n_small).into());
+        }
+
         Ok(Self {
             interner,
-            caves_map: cave_map,
+            indices,
+            neighbours,
+            small_bits,
         })
     }
 }
 
-impl Solution {
-    fn _paths(
-        &self,
-        from: Cave,
-        to: Cave,
-        mut visited_small: SmallVec<[Cave; 12]>,
-        mut visited_twice: Option<Cave>,
-    ) -> usize {
-        if from == to {
-            return 1;
-        }
+fn is_small(name: &str) -> bool {
+    name.chars().all(|c| c.is_ascii_lowercase())
+}
 
-        #[allow(clippy::never_loop)]
-        // the loop here allows to do early break. A bit cryptic, I agree.
-        // Will think about it later
-        loop {
-            if from.is_small {
-                if !visited_small.contains(&from) {
-                    visited_small.push(from);
+/// Position of a path: the current cave, visited small caves and whether
+/// some small cave was already visited twice
+type State = (u32, u64, bool);
 
-                    break;
-                }
+/// Walk between two caves, counting paths from each state once
+struct PathCounter<'a> {
+    solution: &'a Solution,
+    from: u32,
+    to: u32,
+    memo: FxHashMap<State, u128>,
+}
+
+impl PathCounter<'_> {
+    /// Number of paths from the state, `None` if it doesn't fit into u128
+    fn count(&mut self, (cave, visited, visited_twice): State) -> Option<u128> {
+        if cave == self.to {
+            return Some(1);
+        }
+        if let Some(&count) = self.memo.get(&(cave, visited, visited_twice)) {
+            return Some(count);
+        }
 
-                if visited_twice.is_none() && self.interner.resolve(from.symbol) != Some(START) {
-                    visited_twice = Some(from);
-                } else {
-                    return 0;
+        let solution = self.solution;
+        let mut count: u128 = 0;
+        for &next in &solution.neighbours[cave as usize] {
+            let next_state = match solution.small_bits[next as usize] {
+                None => (next, visited, visited_twice),
+                Some(bit) if visited & (1 << bit) == 0 => {
+                    (next, visited | (1 << bit), visited_twice)
                 }
-            }
+                // the first cave can't be visited twice
+                Some(_) if !visited_twice && next != self.from => (next, visited, true),
+                Some(_) => continue,
+            };
 
-            break;
+            count = count.checked_add(self.count(next_state)?)?;
         }
 
-        if let Some(out_caves) = self.caves_map.get(&from) {
-            return out_caves
-                .iter()
-                .map(|&out_cave| self._paths(out_cave, to, visited_small.clone(), visited_twice))
-                .sum();
-        }
+        self.memo.insert((cave, visited, visited_twice), count);
 
-        0
+        Some(count)
     }
+}
 
-    fn paths(&self, from: &str, to: &str, allow_twice: bool) -> Option<usize> {
-        let from_cave = Cave::try_from_str(from, &self.interner)?;
-        let to_cave = Cave::try_from_str(to, &self.interner)?;
-
-        // hack to avoid creating one more function argument
-        let visited_twice = (!allow_twice).then_some(to_cave);
+impl Solution {
+    fn cave(&self, name: &str) -> Option<u32> {
+        self.indices.get(&self.interner.get(name)?).copied()
+    }
 
-        Some(self._paths(
-            from_cave,
-            to_cave,
-            SmallVec::with_capacity(12),
-            visited_twice,
-        ))
+    fn paths(&self, from: &str, to: &str, allow_twice: bool) -> Result<u128, PathsError> {
+        let cave = |name: &str| {
+            self.cave(name)
+                .ok_or_else(|| PathsError::UnknownCave(name.to_owned()))
+        };
+        let (from_cave, to_cave) = (cave(from)?, cave(to)?);
+
+        let visited = self.small_bits[from_cave as usize].map_or(0, |bit| 1 << bit);
+        let mut counter = PathCounter {
+            solution: self,
+            from: from_cave,
+            to: to_cave,
+            memo: FxHashMap::default(),
+        };
+
+        counter
+            .count((from_cave, visited, !allow_twice))
+            .ok_or(PathsError::Overflow)
     }
 }
 
 impl Solver for Solution {
     fn solve(&self, part: Part) -> String {
-        match part {
-            Part::One => {
-                format!(
-                    "there are {} paths from `start` to `end`",
-                    self.paths(START, END, false)
-                        .expect("failed to compute paths")
-                )
-            }
-            Part::Two => {
-                format!(
-                    "there are {} paths from `start` to `end`, if we can visit one small cave twice",
-                    self.paths(START, END, true).expect("failed to compute paths")
-                )
-            }
+        let allow_twice = part == Part::Two;
+
+        match (self.paths(START, END, allow_twice), part) {
+            (Err(e), _) => e.to_string(),
+            (Ok(paths), Part::One) => format!("there are {} paths from `start` to `end`", paths),
+            (Ok(paths), Part::Two) => format!(
+                "there are {} paths from `start` to `end`, if we can visit one small cave twice",
+                paths
+            ),
         }
     }
 
@@ -124,32 +186,6 @@ impl Solver for Solution {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct Cave {
-    symbol: DefaultSymbol,
-    is_small: bool,
-}
-
-impl Cave {
-    fn from_str(s: &str, interner: &mut StringInterner) -> Self {
-        let symbol = interner.get_or_intern(s);
-
-        Self {
-            symbol,
-            is_small: s.chars().all(|c| c.is_ascii_lowercase()),
-        }
-    }
-
-    fn try_from_str(s: &str, interner: &StringInterner) -> Option<Self> {
-        let symbol = interner.get(&s)?;
-
-        Some(Self {
-            symbol,
-            is_small: s.chars().all(|c| c.is_ascii_lowercase()),
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -157,24 +193,87 @@ mod tests {
     #[test]
     fn test_examples_once() {
         let solution: Solution = include_str!("../example-10-36.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, false), Some(10));
+        assert_eq!(solution.paths(START, END, false), Ok(10));
 
         let solution: Solution = include_str!("../example-19-103.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, false), Some(19));
+        assert_eq!(solution.paths(START, END, false), Ok(19));
 
         let solution: Solution = include_str!("../example-226-3509.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, false), Some(226));
+        assert_eq!(solution.paths(START, END, false), Ok(226));
     }
 
     #[test]
     fn test_examples_twice() {
         let solution: Solution = include_str!("../example-10-36.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, true), Some(36));
+        assert_eq!(solution.paths(START, END, true), Ok(36));
 
         let solution: Solution = include_str!("../example-19-103.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, true), Some(103));
+        assert_eq!(solution.paths(START, END, true), Ok(103));
 
         let solution: Solution = include_str!("../example-226-3509.txt").parse().unwrap();
-        assert_eq!(solution.paths(START, END, true), Some(3509));
+        assert_eq!(solution.paths(START, END, true), Ok(3509));
+    }
+
+    /// Two letter name of a small cave
+    fn small_name(i: usize) -> String {
+        format!(
+            "{}{}",
+            (b'a' + (i / 26) as u8) as char,
+            (b'a' + (i % 26) as u8) as char
+        )
+    }
+
+    /// A chain of small caves, each pair of them also connected through `n_big` big caves,
+    /// so there are `n_big + 1` ways to make every step
+    fn chain(n_small: usize, n_big: usize) -> Solution {
+        let mut lines = vec![
+            format!("start-{}", small_name(0)),
+            format!("{}-end", small_name(n_small - 1)),
+        ];
+        for i in 0..n_small - 1 {
+            let (small, next) = (small_name(i), small_name(i + 1));
+            lines.push(format!("{}-{}", small, next));
+            for j in 0..n_big {
+                let big = format!("{}{}", small.to_uppercase(), (b'A' + j as u8) as char);
+                lines.push(format!("{}-{}", small, big));
+                lines.push(format!("{}-{}", big, next));
+            }
+        }
+
+        lines.join("\n").parse().unwrap()
+    }
+
+    #[test]
+    fn test_many_small_caves() {
+        let solution = chain(50, 1);
+        assert_eq!(solution.paths(START, END, false), Ok(1 << 49));
+        assert!(solution.paths(START, END, true).unwrap() > 1 << 49);
+
+        // 62 small caves with `start` and `end` are the most, which fit into the bitmask
+        let solution = chain(62, 3);
+        assert_eq!(solution.paths(START, END, false), Ok(1 << 122));
+        let solution = chain(62, 4);
+        assert_eq!(solution.paths(START, END, false), Err(PathsError::Overflow));
+        assert_eq!(
+            solution.solve(Part::Two),
+            "number of paths doesn't fit into u128"
+        );
+        assert_eq!(
+            solution.paths(START, "nowhere", false),
+            Err(PathsError::UnknownCave("nowhere".to_owned()))
+        );
+
+        let lines = (0..65)
+            .map(|i| format!("A-{}", small_name(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            lines
+                .join("\n")
+                .parse::<Solution>()
+                .unwrap_err()
+                .to_string(),
+            "there are 65 small caves, while at most 64 are supported"
+        );
+        assert!("start-A\nA-B\nB-end".parse::<Solution>().is_err());
     }
 }